@@ -1,14 +0,0 @@
-#[macro_export]
-macro_rules! AI {
-    () => {
-        todo!("AI generation not yet implemented")
-    };
-    ($($arg:tt)*) => {
-        todo!("AI generation not yet implemented")
-    };
-}
-
-#[cfg(test)]
-mod tests {}
-
-//credit signature:
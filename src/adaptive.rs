@@ -0,0 +1,280 @@
+use crate::EvictionPolicy;
+use crate::RemovalCause;
+use crate::Slots;
+use crate::ghost::Ghosts;
+
+/// end of a list in `Lru::newer` and `Lru::older`
+const NIL: usize = usize::MAX;
+
+/// slots from least to most recently used, linked in place
+struct Lru {
+    newer: Vec<usize>,
+    older: Vec<usize>,
+    member: Vec<bool>,
+    newest: usize,
+    oldest: usize,
+    len: usize,
+}
+
+impl Lru {
+    fn new(capacity: usize) -> Self {
+        Self {
+            newer: vec![NIL; capacity],
+            older: vec![NIL; capacity],
+            member: vec![false; capacity],
+            newest: NIL,
+            oldest: NIL,
+            len: 0,
+        }
+    }
+
+    fn contains(&self, slot: usize) -> bool {
+        self.member[slot]
+    }
+
+    fn push(&mut self, slot: usize) {
+        self.older[slot] = self.newest;
+        self.newer[slot] = NIL;
+        match self.newest {
+            NIL => self.oldest = slot,
+            newest => self.newer[newest] = slot,
+        }
+        self.newest = slot;
+        self.member[slot] = true;
+        self.len += 1;
+    }
+
+    fn remove(&mut self, slot: usize) {
+        let (older, newer) = (self.older[slot], self.newer[slot]);
+        match older {
+            NIL => self.oldest = newer,
+            older => self.newer[older] = newer,
+        }
+        match newer {
+            NIL => self.newest = older,
+            newer => self.older[newer] = older,
+        }
+        self.member[slot] = false;
+        self.len -= 1;
+    }
+
+    /// put `slot` back at the least recently used end
+    fn push_oldest(&mut self, slot: usize) {
+        self.older[slot] = NIL;
+        self.newer[slot] = self.oldest;
+        match self.oldest {
+            NIL => self.newest = slot,
+            oldest => self.older[oldest] = slot,
+        }
+        self.oldest = slot;
+        self.member[slot] = true;
+        self.len += 1;
+    }
+
+    fn pop(&mut self) -> Option<usize> {
+        let slot = self.oldest;
+        (slot != NIL).then(|| {
+            self.remove(slot);
+            slot
+        })
+    }
+
+    fn clear(&mut self) {
+        *self = Self::new(self.member.len());
+    }
+}
+
+#[doc = r#"
+# Feature
+- **Recency and frequency**
+- **Ghost lists**
+- **Adaptive target**
+
+# Example
+## Recency and frequency
+a new key enter `recent`, an applied read move it to the newest end of `frequent`, a scan of
+keys read once only ever churn `recent`
+```
+use dual_cache_ff::{Adaptive, DualCacheFF};
+
+let cache = DualCacheFF::builder()
+    .capacity(4)
+    .policy::<Adaptive>()
+    .build_manual()
+    .unwrap();
+cache.put(0, 0);
+cache.put(1, 1);
+cache.get(&0);
+cache.get(&1);
+cache.run_pending_tasks();
+for i in 100..200 {
+    cache.put(i, i);
+}
+
+assert_eq!(cache.get(&0), Some(0));
+assert_eq!(cache.get(&1), Some(1));
+```
+
+## Ghost lists
+the key hashes evicted from `recent` and `frequent` are kept in `recent_ghosts` and
+`frequent_ghosts`, `recent` and its ghosts hold at most one capacity of keys, all four lists
+together two
+
+## Adaptive target
+`target` is the size `recent` is allowed to keep, a key written again while in
+`recent_ghosts` grow it and one in `frequent_ghosts` shrink it, taking the place of the
+fixed `evict_point` calibration of `FifoProbation`
+"#]
+pub struct Adaptive {
+    recent: Lru,
+    frequent: Lru,
+    recent_ghosts: Ghosts,
+    frequent_ghosts: Ghosts,
+    pub(crate) target: usize,
+    /// candidate of the last `victim` whose ghost already moved `target`
+    adapted: Option<u64>,
+    /// `target` before the last `victim` adapted it and whether that victim left `recent`
+    undo: Option<(usize, bool)>,
+    capacity: usize,
+}
+
+impl Adaptive {
+    /// move `target` toward the list `hash` was evicted from, if it is a ghost
+    fn adapt(&mut self, hash: u64) {
+        let (recent, frequent) = (self.recent_ghosts.len(), self.frequent_ghosts.len());
+        if self.recent_ghosts.contains(hash) {
+            let delta = (frequent / recent).max(1);
+            self.target = (self.target + delta).min(self.capacity);
+        } else if self.frequent_ghosts.contains(hash) {
+            let delta = (recent / frequent).max(1);
+            self.target = self.target.saturating_sub(delta);
+        }
+    }
+
+    /// keep at most one capacity in `recent` with its ghosts, two in total
+    fn trim(&mut self) {
+        while self.recent.len + self.recent_ghosts.len() > self.capacity
+            && self.recent_ghosts.len() > 0
+        {
+            self.recent_ghosts.pop();
+        }
+        while self.recent.len
+            + self.frequent.len
+            + self.recent_ghosts.len()
+            + self.frequent_ghosts.len()
+            > 2 * self.capacity
+            && self.frequent_ghosts.len() > 0
+        {
+            self.frequent_ghosts.pop();
+        }
+    }
+}
+
+impl EvictionPolicy for Adaptive {
+    fn with_capacity(capacity: usize) -> Self {
+        Self {
+            recent: Lru::new(capacity),
+            frequent: Lru::new(capacity),
+            recent_ghosts: Ghosts::default(),
+            frequent_ghosts: Ghosts::default(),
+            target: 0,
+            adapted: None,
+            undo: None,
+            capacity,
+        }
+    }
+
+    fn on_insert(&mut self, slot: usize, slots: &mut Slots<'_>) {
+        let adapted = self.adapted.take();
+        let Some(hash) = slots.hash(slot) else {
+            return;
+        };
+        if adapted != Some(hash) {
+            self.adapt(hash);
+        }
+        if self.recent_ghosts.remove(hash) || self.frequent_ghosts.remove(hash) {
+            self.frequent.push(slot);
+        } else {
+            self.recent.push(slot);
+        }
+        self.trim();
+    }
+
+    fn on_access(&mut self, slot: usize, _: &mut Slots<'_>) {
+        if self.recent.contains(slot) {
+            self.recent.remove(slot);
+        } else if self.frequent.contains(slot) {
+            self.frequent.remove(slot);
+        } else {
+            return;
+        }
+        self.frequent.push(slot);
+    }
+
+    fn on_remove(&mut self, vacated: &[usize], _: &mut Slots<'_>) {
+        for &slot in vacated {
+            if self.recent.contains(slot) {
+                self.recent.remove(slot);
+            } else if self.frequent.contains(slot) {
+                self.frequent.remove(slot);
+            }
+        }
+    }
+
+    /// evict from `recent` while it is over `target`, from `frequent` otherwise, remembering
+    /// the key hash in the matching ghost list
+    fn victim(&mut self, candidate: u64, slots: &mut Slots<'_>) -> (usize, RemovalCause) {
+        let target = self.target;
+        self.adapt(candidate);
+        self.adapted = Some(candidate);
+        let over = self.recent.len > self.target
+            || (self.frequent_ghosts.contains(candidate) && self.recent.len == self.target);
+        let from_recent = self.recent.len > 0 && (over || self.frequent.len == 0);
+        self.undo = Some((target, from_recent));
+        let (list, ghosts) = if from_recent {
+            (&mut self.recent, &mut self.recent_ghosts)
+        } else {
+            (&mut self.frequent, &mut self.frequent_ghosts)
+        };
+        let Some(slot) = list.pop() else {
+            self.undo = None;
+            return (0, RemovalCause::Overwritten);
+        };
+        if slots.outdated(slot) {
+            return (slot, RemovalCause::Expired);
+        }
+        if let Some(hash) = slots.hash(slot) {
+            ghosts.push(hash);
+        }
+        (slot, RemovalCause::Evicted)
+    }
+
+    /// restore `target`, drop the ghost and seat the slot back as the next victim of its list,
+    /// the refused candidate adapt again if it come back
+    fn on_reject(&mut self, slot: usize, slots: &mut Slots<'_>) {
+        self.adapted = None;
+        let Some((target, from_recent)) = self.undo.take() else {
+            return;
+        };
+        self.target = target;
+        let (list, ghosts) = if from_recent {
+            (&mut self.recent, &mut self.recent_ghosts)
+        } else {
+            (&mut self.frequent, &mut self.frequent_ghosts)
+        };
+        if let Some(hash) = slots.hash(slot) {
+            ghosts.remove(hash);
+        }
+        list.push_oldest(slot);
+    }
+
+    fn clear(&mut self) {
+        self.recent.clear();
+        self.frequent.clear();
+        self.recent_ghosts.clear();
+        self.frequent_ghosts.clear();
+        self.target = 0;
+        self.adapted = None;
+        self.undo = None;
+    }
+}
@@ -0,0 +1,253 @@
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use anyhow::ensure;
+
+use crate::CacheLoader;
+use crate::CacheWriter;
+use crate::Clock;
+use crate::Config;
+use crate::Daemon;
+use crate::DualCacheFF;
+use crate::EvictionListener;
+use crate::EvictionPolicy;
+use crate::Expiry;
+use crate::FifoProbation;
+use crate::Hooks;
+use crate::WriteMode;
+
+/// cache handed back by `Builder::build` with the handle of its daemon
+type Spawned<K, V, P> = (Arc<DualCacheFF<K, V, P>>, Daemon);
+
+#[doc = r#"
+# Feature
+- **Typed duration**
+- **Validation**
+- **Daemon or manual**
+
+# Example
+## Typed duration
+`time_to_live` and `time_to_idle` take `std::time::Duration`, no more guessing second or
+millisecond, an entry expire on whichever come first
+```
+use std::time::Duration;
+use dual_cache_ff::DualCacheFF;
+
+let (cache, daemon) = DualCacheFF::builder()
+    .capacity(1_000)
+    .time_to_live(Duration::from_secs(60))
+    .time_to_idle(Duration::from_millis(500))
+    .channel_bound(1_024)
+    .thread_name("session-cache")
+    .build()
+    .unwrap();
+cache.put("A", 100);
+
+assert_eq!(cache.get("A"), Some(100));
+daemon.shutdown().unwrap();
+```
+
+## Validation
+zero capacity, zero ttl or tti, zero channel bound or an empty batch range fail on `build`
+instead of panicking inside the ring
+```
+use dual_cache_ff::DualCacheFF;
+
+let err = DualCacheFF::<u32, u32>::builder()
+    .capacity(0)
+    .build_manual()
+    .err().unwrap();
+
+assert_eq!(err.to_string(), "capacity must be greater than zero");
+```
+
+## Daemon or manual
+`build` spawn the daemon thread and hand back its `Daemon`, `build_manual` spawn nothing and
+leave `run_pending_tasks` to the caller
+"#]
+pub struct Builder<K, V, P = FifoProbation> {
+    config: Config,
+    hooks: Hooks<K, V>,
+    thread_name: String,
+    marker: PhantomData<fn() -> (K, V)>,
+    policy: PhantomData<fn() -> P>,
+}
+
+impl<K, V, P> Default for Builder<K, V, P> {
+    fn default() -> Self {
+        Self {
+            config: Config::default(),
+            hooks: Hooks::default(),
+            thread_name: "dual-cache-ff".to_string(),
+            marker: PhantomData,
+            policy: PhantomData,
+        }
+    }
+}
+
+impl<K, V, P> Builder<K, V, P>
+where
+    K: Hash + Eq + Clone + Send + Sync + 'static + Debug,
+    V: Clone + Send + Sync + 'static + Debug,
+    P: EvictionPolicy,
+{
+    /// number of `nodes` slots in the ring
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.config.capacity = capacity;
+        self
+    }
+
+    /// lifetime of an entry counted from its last `put`
+    pub fn time_to_live(mut self, duration: Duration) -> Self {
+        self.config.duration = duration;
+        self
+    }
+
+    /// lifetime of an entry counted from its last read or write, next to `time_to_live`,
+    /// reads only count once the daemon or `run_pending_tasks` applied them
+    pub fn time_to_idle(mut self, idle: Duration) -> Self {
+        self.config.idle = Some(idle);
+        self
+    }
+
+    /// age past which a `get` still return the value but schedule its reload through the
+    /// `loader`, so hot keys are replaced before they expire
+    pub fn refresh_after(mut self, refresh: Duration) -> Self {
+        self.config.refresh = Some(refresh);
+        self
+    }
+
+    /// keep outdated entries for `grace` past their lifetime, `get_or_load_stale` serve them
+    /// while the `loader` fail
+    pub fn serve_stale(mut self, grace: Duration) -> Self {
+        self.config.grace = Some(grace);
+        self
+    }
+
+    /// per node lifetimes decided by key and value, in place of `time_to_live`
+    pub fn expiry(mut self, expiry: impl Expiry<K, V> + 'static) -> Self {
+        self.hooks.expiry = Some(Arc::new(expiry));
+        self
+    }
+
+    /// time source of every `epoch` and expiry check, `MonotonicClock` by default
+    pub fn clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.hooks.clock = Some(Arc::new(clock));
+        self
+    }
+
+    /// bound of the lazy promotion channel, promotions beyond it are dropped
+    pub fn channel_bound(mut self, bound: usize) -> Self {
+        self.config.bound = bound;
+        self
+    }
+
+    /// keys the daemon drains per batch, adjusted inside `min..=max`
+    pub fn batch(mut self, min: usize, max: usize) -> Self {
+        self.config.min_batch = min;
+        self.config.max_batch = max;
+        self
+    }
+
+    /// number of batches the daemon throughput SMA averages over
+    pub fn window(mut self, window: usize) -> Self {
+        self.config.window = window;
+        self
+    }
+
+    /// name of the spawned daemon thread
+    pub fn thread_name(mut self, name: impl Into<String>) -> Self {
+        self.thread_name = name.into();
+        self
+    }
+
+    /// read-through source used by `DualCacheFF::read_through` and `get_or_load` on miss
+    pub fn loader(mut self, loader: impl CacheLoader<K, V> + 'static) -> Self {
+        self.hooks.loader = Some(Arc::new(loader));
+        self
+    }
+
+    /// backing store `put` and `remove` propagate to, synchronously or from the daemon
+    pub fn writer(mut self, writer: impl CacheWriter<K, V> + 'static, mode: WriteMode) -> Self {
+        self.hooks.writer = Some((Arc::new(writer), mode));
+        self
+    }
+
+    /// callback told of every node leaving the cache and why, run by the daemon
+    pub fn eviction_listener(mut self, listener: impl EvictionListener<K, V> + 'static) -> Self {
+        self.hooks.listener = Some(Arc::new(listener));
+        self
+    }
+
+    /// W-TinyLFU admission, a key about to evict a live node is dropped instead unless its
+    /// sketched frequency beat the victim's, keeping one-hit wonders from flushing hot keys
+    pub fn tiny_lfu(mut self) -> Self {
+        self.config.admission = true;
+        self
+    }
+
+    /// replacement policy deciding which node a full cache overwrite, `FifoProbation` by default
+    pub fn policy<Q: EvictionPolicy>(self) -> Builder<K, V, Q> {
+        Builder {
+            config: self.config,
+            hooks: self.hooks,
+            thread_name: self.thread_name,
+            marker: PhantomData,
+            policy: PhantomData,
+        }
+    }
+
+    /// build the cache and spawn its daemon thread
+    pub fn build(self) -> Result<Spawned<K, V, P>> {
+        self.validate()?;
+        Ok(DualCacheFF::spawn(
+            self.config,
+            self.hooks,
+            self.thread_name,
+        )?)
+    }
+
+    /// build the cache without any thread, see `DualCacheFF::run_pending_tasks`
+    pub fn build_manual(self) -> Result<Arc<DualCacheFF<K, V, P>>> {
+        self.validate()?;
+        Ok(DualCacheFF::manual(self.config, self.hooks))
+    }
+
+    fn validate(&self) -> Result<()> {
+        let config = &self.config;
+        ensure!(config.capacity > 0, "capacity must be greater than zero");
+        ensure!(
+            !config.duration.is_zero(),
+            "time to live must be greater than zero"
+        );
+        ensure!(
+            config.idle.is_none_or(|idle| !idle.is_zero()),
+            "time to idle must be greater than zero"
+        );
+        ensure!(
+            config.refresh.is_none_or(|refresh| !refresh.is_zero()),
+            "refresh after must be greater than zero"
+        );
+        ensure!(
+            config.refresh.is_none() || self.hooks.loader.is_some(),
+            "refresh after needs a CacheLoader"
+        );
+        ensure!(
+            config.grace.is_none_or(|grace| !grace.is_zero()),
+            "grace period must be greater than zero"
+        );
+        ensure!(config.bound > 0, "channel bound must be greater than zero");
+        ensure!(
+            0 < config.min_batch && config.min_batch <= config.max_batch,
+            "batch range {}..={} must be non-empty and start above zero",
+            config.min_batch,
+            config.max_batch
+        );
+        ensure!(config.window > 0, "window must be greater than zero");
+        Ok(())
+    }
+}
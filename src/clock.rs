@@ -0,0 +1,117 @@
+use std::time::Duration;
+use std::time::Instant;
+
+#[doc = r#"
+# Feature
+- **Monotonic**
+- **Injectable**
+
+# Example
+## Monotonic
+every `epoch`, `access` and expiry check read `Clock::now`, the default `MonotonicClock` count
+from an `Instant` so a wall clock jump neither expire nor revive entries
+
+## Injectable
+registered by `Builder::clock`, `MockClock` of the `test-util` feature drive ttl and tti
+without sleeping
+"#]
+pub trait Clock: Send + Sync {
+    /// time elapsed since an origin fixed by the clock, never going backward
+    fn now(&self) -> Duration;
+}
+
+impl<C: Clock + ?Sized> Clock for std::sync::Arc<C> {
+    fn now(&self) -> Duration {
+        (**self).now()
+    }
+}
+
+/// default `Clock`, time elapsed since its creation
+pub struct MonotonicClock {
+    origin: Instant,
+}
+
+impl MonotonicClock {
+    pub fn new() -> Self {
+        Self {
+            origin: Instant::now(),
+        }
+    }
+}
+
+impl Default for MonotonicClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for MonotonicClock {
+    fn now(&self) -> Duration {
+        self.origin.elapsed()
+    }
+}
+
+#[cfg(any(test, feature = "test-util"))]
+pub use mock::MockClock;
+
+#[cfg(any(test, feature = "test-util"))]
+mod mock {
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    use super::Clock;
+
+    #[doc = r#"
+    # Feature
+    - **Manual time**
+
+    # Example
+    ## Manual time
+    start at zero and only move on `advance` or `set`, share it through `Arc` to keep a handle
+    ```
+    use std::sync::Arc;
+    use std::time::Duration;
+    use dual_cache_ff::{DualCacheFF, MockClock};
+
+    let clock = Arc::new(MockClock::new());
+    let cache = DualCacheFF::builder()
+        .clock(clock.clone())
+        .time_to_live(Duration::from_secs(60))
+        .build_manual()
+        .unwrap();
+    cache.put("A", 100);
+
+    clock.advance(Duration::from_secs(59));
+    assert_eq!(cache.get("A"), Some(100));
+    clock.advance(Duration::from_secs(1));
+    assert!(cache.get("A").is_none());
+    ```
+    "#]
+    #[derive(Default)]
+    pub struct MockClock {
+        now: Mutex<Duration>,
+    }
+
+    impl MockClock {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        pub fn advance(&self, by: Duration) {
+            let mut now = self.now.lock().unwrap();
+            *now = now.saturating_add(by);
+        }
+
+        /// jump to `at`, going backward is ignored as `Clock` is monotonic
+        pub fn set(&self, at: Duration) {
+            let mut now = self.now.lock().unwrap();
+            *now = at.max(*now);
+        }
+    }
+
+    impl Clock for MockClock {
+        fn now(&self) -> Duration {
+            *self.now.lock().unwrap()
+        }
+    }
+}
@@ -0,0 +1,175 @@
+use std::convert::Infallible;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use tracing::warn;
+
+use crate::DualCacheFF;
+use crate::EvictionPolicy;
+use crate::FifoProbation;
+use crate::writer::KeyGuard;
+
+#[doc = r#"
+# Feature
+- **Atomic lookup and insert**
+- **Fresh only**
+- **Writer**
+- **Producer panic**
+
+# Example
+## Atomic lookup and insert
+the lock of the key is held from `DualCacheFF::entry` until the entry is consumed, so the
+producer run at most once per miss and no `put` or `remove` of the key slip in between, `main`
+is only taken to look up and to apply the change, other keys go on meanwhile, a producer that
+`put`, `remove` or `entry` its own key wait on itself
+```
+use dual_cache_ff::DualCacheFF;
+
+let cache = DualCacheFF::new();
+assert_eq!(cache.entry("A").or_insert(1), 1);
+assert_eq!(cache.entry("A").and_modify(|value| *value += 1).or_insert(1), 2);
+assert_eq!(cache.entry("B").or_insert_with(|| 10), 10);
+
+let loaded = cache
+    .entry("C")
+    .or_try_insert_with(|| Err::<u32, _>(anyhow::anyhow!("unavailable")));
+assert!(loaded.is_err());
+assert!(cache.get("C").is_none());
+```
+
+## Fresh only
+an outdated node count as vacant, `and_modify` skip it and `or_insert*` overwrite it
+
+## Writer
+inserted and modified values reach a configured `CacheWriter` before the cache like `put`, a
+value refused by write-through is not cached, `or_try_insert_with` hand the failure back and
+the others log it
+
+## Producer panic
+a panicking producer or `modify` leave the node as it was, neither run under `main` and the
+key is released while unwinding
+```
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use dual_cache_ff::DualCacheFF;
+
+let cache = DualCacheFF::new();
+let panicked = catch_unwind(AssertUnwindSafe(|| {
+    cache.entry("A").or_insert_with(|| panic!("producer"))
+}));
+
+assert!(panicked.is_err());
+cache.put("A", 1);
+assert_eq!(cache.get("A"), Some(1));
+```
+"#]
+pub struct Entry<'a, K, V, P = FifoProbation>
+where
+    K: Hash + Eq + Clone + Send + Sync + 'static + Debug,
+    V: Clone + Send + Sync + 'static + Debug,
+    P: EvictionPolicy,
+{
+    cache: &'a DualCacheFF<K, V, P>,
+    _guard: KeyGuard<'a>,
+    key: K,
+}
+
+impl<'a, K, V, P> Entry<'a, K, V, P>
+where
+    K: Hash + Eq + Clone + Send + Sync + 'static + Debug,
+    V: Clone + Send + Sync + 'static + Debug,
+    P: EvictionPolicy,
+{
+    pub(crate) fn new(cache: &'a DualCacheFF<K, V, P>, key: K) -> Self {
+        Self {
+            cache,
+            _guard: cache.keys.lock(&key),
+            key,
+        }
+    }
+
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    /// modify the fresh value in place, counted as a write so its lifetime restart
+    pub fn and_modify(self, modify: impl FnOnce(&mut V)) -> Self {
+        let Some(mut value) = self.lookup() else {
+            return self;
+        };
+        modify(&mut value);
+        if let Err(err) = self.cache.propagate(&self.key, Some(&value)) {
+            warn!(%err, key = ?self.key, "write-through failed, value not modified");
+            return self;
+        }
+        let mut state = self.cache.main.lock().unwrap();
+        let epoch = state.clock.now();
+        // dropped meanwhile by eviction or `invalidate`, not brought back
+        if let Some(&slot) = state.index.get(&self.key)
+            && state.nodes[slot].is_some()
+        {
+            state.renew(slot, epoch, None, |slot_value| *slot_value = value);
+            self.cache.mirror.publish(&mut state);
+        }
+        self
+    }
+
+    pub fn or_insert(self, default: V) -> V {
+        self.or_insert_with(|| default)
+    }
+
+    /// a value refused by write-through is logged and handed back uncached, like `put`
+    pub fn or_insert_with(self, default: impl FnOnce() -> V) -> V {
+        let inserted = self.insert_with(
+            || Ok::<_, Infallible>(default()),
+            |key, value, err| {
+                warn!(%err, ?key, "write-through failed, value not cached");
+                Ok(value)
+            },
+        );
+        match inserted {
+            Ok(value) => value,
+            Err(never) => match never {},
+        }
+    }
+
+    /// insert the produced value on miss, an `Err` of the producer or of write-through is
+    /// handed back and nothing is cached
+    pub fn or_try_insert_with<E: From<anyhow::Error>>(
+        self,
+        default: impl FnOnce() -> Result<V, E>,
+    ) -> Result<V, E> {
+        self.insert_with(default, |_, _, err| Err(E::from(err)))
+    }
+
+    /// `refused` decide the outcome once write-through turned the produced value down
+    fn insert_with<E>(
+        self,
+        default: impl FnOnce() -> Result<V, E>,
+        refused: impl FnOnce(&K, V, anyhow::Error) -> Result<V, E>,
+    ) -> Result<V, E> {
+        if let Some(value) = self.lookup() {
+            // promotion is only a hint, drop it rather than block the writer
+            let _ = self.cache.lazy_tx.try_send(self.key.clone());
+            return Ok(value);
+        }
+
+        let value = default()?;
+        if let Err(err) = self.cache.propagate(&self.key, Some(&value)) {
+            return refused(&self.key, value, err);
+        }
+        let mut state = self.cache.main.lock().unwrap();
+        let epoch = state.clock.now();
+        state.insert(self.key.clone(), value.clone(), epoch, None);
+        self.cache.mirror.publish(&mut state);
+        Ok(value)
+    }
+
+    /// copy of the fresh value, `main` released on return
+    fn lookup(&self) -> Option<V> {
+        let state = self.cache.main.lock().unwrap();
+        let epoch = state.clock.now();
+        state
+            .lookup(&self.key, epoch)
+            .map(|node| node.value.clone())
+    }
+}
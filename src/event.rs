@@ -0,0 +1,88 @@
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use crossbeam::channel::Receiver;
+use crossbeam::channel::Sender;
+use crossbeam::channel::TrySendError;
+use crossbeam::channel::bounded;
+
+use crate::DualCacheFF;
+use crate::EvictionPolicy;
+use crate::RemovalCause;
+
+/// senders of every live `DualCacheFF::subscribe`, kept in `Cache` under `main`
+pub(crate) type Subscribers<K, V> = Vec<Sender<Event<K, V>>>;
+
+/// mutation seen by a subscriber, carrying the value it concern
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Event<K, V> {
+    /// a key written into a vacant or reclaimed slot
+    Insert(K, V),
+    /// a present key written over, with its new value
+    Update(K, V),
+    /// dropped by `remove`, `invalidate*` or `clear`
+    Remove(K, V),
+    /// pushed out by the ring, probation or expiration
+    Evict(K, V, RemovalCause),
+}
+
+/// hand `event` to every subscriber without blocking, a full one miss it and a disconnected
+/// one is forgotten
+pub(crate) fn publish<K: Clone, V: Clone>(
+    subscribers: &mut Subscribers<K, V>,
+    event: impl FnOnce() -> Event<K, V>,
+) {
+    if subscribers.is_empty() {
+        return;
+    }
+    let event = event();
+    subscribers.retain(|tx| {
+        !matches!(
+            tx.try_send(event.clone()),
+            Err(TrySendError::Disconnected(_))
+        )
+    });
+}
+
+impl<K, V, P> DualCacheFF<K, V, P>
+where
+    K: Hash + Eq + Clone + Send + Sync + 'static + Debug,
+    V: Clone + Send + Sync + 'static + Debug,
+    P: EvictionPolicy,
+{
+    #[doc = r#"
+    # Feature
+    - **Change stream**
+    - **Lag policy**
+
+    # Example
+    ## Change stream
+    every mutation under `main` is sent as an `Event` in the order it was applied, eviction
+    and expiration included
+    ```
+    use dual_cache_ff::{DualCacheFF, Event};
+
+    let cache = DualCacheFF::builder().build_manual().unwrap();
+    let events = cache.subscribe();
+    cache.put("A", 1);
+    cache.put("A", 2);
+    cache.remove("A");
+
+    assert_eq!(
+        events.try_iter().collect::<Vec<_>>(),
+        [Event::Insert("A", 1), Event::Update("A", 2), Event::Remove("A", 2)]
+    );
+    ```
+
+    ## Lag policy
+    each subscriber buffer up to `channel_bound` events, once full the newest events are
+    dropped for it alone, the cache never wait on a reader, dropping the `Receiver`
+    unsubscribe on the next mutation
+    "#]
+    pub fn subscribe(&self) -> Receiver<Event<K, V>> {
+        let mut state = self.main.lock().unwrap();
+        let (tx, rx) = bounded(state.config.bound);
+        state.subscribers.push(tx);
+        rx
+    }
+}
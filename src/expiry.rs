@@ -0,0 +1,74 @@
+use std::panic::AssertUnwindSafe;
+use std::time::Duration;
+
+use tracing::warn;
+
+#[doc = r#"
+# Feature
+- **Create**
+- **Read**
+- **Update**
+- **Panic**
+
+# Example
+## Create
+registered by `Builder::expiry`, the lifetime of a new node come from `expire_after_create`
+instead of `config.duration`, `put_with_ttl` still win over it
+```
+use std::time::Duration;
+use dual_cache_ff::{DualCacheFF, Expiry};
+
+struct MaxAge;
+
+impl Expiry<&'static str, (u64, &'static str)> for MaxAge {
+    fn expire_after_create(&self, _: &&'static str, value: &(u64, &'static str)) -> Duration {
+        Duration::from_secs(value.0)
+    }
+}
+
+let cache = DualCacheFF::builder().expiry(MaxAge).build_manual().unwrap();
+cache.put("/index.html", (0, "<html>"));
+cache.put("/logo.png", (86_400, "png"));
+
+assert!(cache.get("/index.html").is_none());
+assert!(cache.get("/logo.png").is_some());
+```
+
+## Read
+applied with the promotion by the daemon or `run_pending_tasks`, default keep `remaining`
+
+## Update
+a write over a fresh node, default restart as `expire_after_create`
+
+## Panic
+callbacks run under `main`, a panicking one is logged and the node keep `config.duration` on
+create and update, its remaining lifetime on read
+"#]
+pub trait Expiry<K, V>: Send + Sync {
+    /// lifetime of a node counted from its insertion
+    fn expire_after_create(&self, key: &K, value: &V) -> Duration;
+
+    /// lifetime left after a read, `remaining` is what was left before it
+    fn expire_after_read(&self, key: &K, value: &V, remaining: Duration) -> Duration {
+        let _ = (key, value);
+        remaining
+    }
+
+    /// lifetime of a node counted from its overwrite, `remaining` is what was left before it
+    fn expire_after_update(&self, key: &K, value: &V, remaining: Duration) -> Duration {
+        let _ = remaining;
+        self.expire_after_create(key, value)
+    }
+}
+
+/// run one `Expiry` callback, a panic is logged and `fallback` used so `main` is never poisoned
+pub(crate) fn guarded(
+    callback: &str,
+    lifetime: impl FnOnce() -> Duration,
+    fallback: Duration,
+) -> Duration {
+    std::panic::catch_unwind(AssertUnwindSafe(lifetime)).unwrap_or_else(|_| {
+        warn!(callback, "expiry panicked, fallback lifetime used");
+        fallback
+    })
+}
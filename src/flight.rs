@@ -0,0 +1,179 @@
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::sync::Arc;
+use std::sync::Condvar;
+use std::sync::Mutex;
+
+use anyhow::anyhow;
+
+use crate::DualCacheFF;
+use crate::EvictionPolicy;
+
+type Outcome<V> = Result<V, Arc<anyhow::Error>>;
+
+/// one in-progress load of a key, waiters block until the leader resolve it
+pub(crate) struct Flight<V> {
+    outcome: Mutex<Option<Outcome<V>>>,
+    done: Condvar,
+}
+
+impl<V: Clone> Flight<V> {
+    fn new() -> Self {
+        Self {
+            outcome: Mutex::new(None),
+            done: Condvar::new(),
+        }
+    }
+
+    /// first outcome wins, later ones are ignored
+    fn resolve(&self, outcome: Outcome<V>) {
+        let mut slot = self.outcome.lock().unwrap();
+        if slot.is_none() {
+            *slot = Some(outcome);
+            self.done.notify_all();
+        }
+    }
+
+    fn wait(&self) -> Outcome<V> {
+        let slot = self
+            .done
+            .wait_while(self.outcome.lock().unwrap(), |slot| slot.is_none())
+            .unwrap();
+        slot.clone().expect("resolved before notify")
+    }
+}
+
+/// unregister the flight once the leader is done, failing it if the loader panicked
+struct Leader<'a, K, V, P>
+where
+    K: Hash + Eq + Clone + Send + Sync + 'static + Debug,
+    V: Clone + Send + Sync + 'static + Debug,
+    P: EvictionPolicy,
+{
+    cache: &'a DualCacheFF<K, V, P>,
+    key: K,
+    flight: Arc<Flight<V>>,
+}
+
+impl<K, V, P> Drop for Leader<'_, K, V, P>
+where
+    K: Hash + Eq + Clone + Send + Sync + 'static + Debug,
+    V: Clone + Send + Sync + 'static + Debug,
+    P: EvictionPolicy,
+{
+    fn drop(&mut self) {
+        self.flight
+            .resolve(Err(Arc::new(anyhow!("loader of {:?} panicked", self.key))));
+        self.cache.flights.lock().unwrap().remove(&self.key);
+    }
+}
+
+impl<K, V, P> DualCacheFF<K, V, P>
+where
+    K: Hash + Eq + Clone + Send + Sync + 'static + Debug,
+    V: Clone + Send + Sync + 'static + Debug,
+    P: EvictionPolicy,
+{
+    #[doc = r#"
+    # Feature
+    - **Single flight**
+    - **Put path**
+
+    # Example
+    ## Single flight
+    concurrent misses of the same key run `loader` once, the others block on its result
+    ```
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+    use dual_cache_ff::DualCacheFF;
+
+    let cache = DualCacheFF::new();
+    let calls = AtomicUsize::new(0);
+    thread::scope(|scope| {
+        for _ in 0..4 {
+            scope.spawn(|| {
+                let value = cache.get_with("A", || {
+                    calls.fetch_add(1, Ordering::SeqCst);
+                    100
+                });
+                assert_eq!(value, 100);
+            });
+        }
+    });
+
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+    ```
+
+    ## Put path
+    the loaded value go through the `put` path, so it is visible to `get` afterwards, without
+    being written back to a `CacheWriter`
+    "#]
+    pub fn get_with(&self, key: K, loader: impl FnOnce() -> V) -> V {
+        let mut loader = Some(loader);
+        loop {
+            // only a waiter sees an error, from a fallible leader, so try again as leader
+            if let Ok(value) = self.try_get_with(key.clone(), || {
+                Ok(loader.take().expect("leader run loader once")())
+            }) {
+                return value;
+            }
+        }
+    }
+
+    #[doc = r#"
+    # Feature
+    - **Error fan out**
+
+    # Example
+    ## Error fan out
+    an `Err` from `loader` reach the leader and every waiter of that flight, nothing is cached
+    ```
+    use anyhow::anyhow;
+    use dual_cache_ff::DualCacheFF;
+
+    let cache = DualCacheFF::<&str, u32>::new();
+    let err = cache.try_get_with("A", || Err(anyhow!("database down"))).err().unwrap();
+
+    assert_eq!(err.to_string(), "database down");
+    assert!(cache.get("A").is_none());
+    assert_eq!(cache.try_get_with("A", || Ok(100)).unwrap(), 100);
+    ```
+    "#]
+    pub fn try_get_with(
+        &self,
+        key: K,
+        loader: impl FnOnce() -> anyhow::Result<V>,
+    ) -> Result<V, Arc<anyhow::Error>> {
+        if let Some(value) = self.hit(&key) {
+            return Ok(value);
+        }
+
+        let flight = {
+            let mut flights = self.flights.lock().unwrap();
+            if let Some(flight) = flights.get(&key) {
+                let flight = flight.clone();
+                drop(flights);
+                return flight.wait();
+            }
+            let flight = Arc::new(Flight::new());
+            flights.insert(key.clone(), flight.clone());
+            flight
+        };
+        let leader = Leader {
+            cache: self,
+            key,
+            flight,
+        };
+
+        // a previous leader may have landed between the miss and the registration
+        let outcome = match self.hit(&leader.key) {
+            Some(value) => Ok(value),
+            None => loader()
+                .map_err(Arc::new)
+                .inspect(|value| self.fill(leader.key.clone(), value.clone())),
+        };
+        leader.flight.resolve(outcome.clone());
+
+        outcome
+    }
+}
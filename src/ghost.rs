@@ -0,0 +1,63 @@
+use std::collections::HashMap;
+use std::collections::VecDeque;
+
+/// key hashes of evicted nodes, oldest first, a removed hash stay in `order` until it reach the
+/// front or a compaction drop it
+#[derive(Default)]
+pub(crate) struct Ghosts {
+    /// hash and the sequence it was pushed at, stale entries included
+    order: VecDeque<(u64, u64)>,
+    /// live hashes and the sequence of their entry in `order`
+    members: HashMap<u64, u64>,
+    sequence: u64,
+}
+
+impl Ghosts {
+    pub(crate) fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    pub(crate) fn contains(&self, hash: u64) -> bool {
+        self.members.contains_key(&hash)
+    }
+
+    /// remember `hash` as the newest ghost, one already remembered keep its place
+    pub(crate) fn push(&mut self, hash: u64) {
+        if self.members.contains_key(&hash) {
+            return;
+        }
+        self.sequence += 1;
+        self.members.insert(hash, self.sequence);
+        self.order.push_back((hash, self.sequence));
+    }
+
+    /// forget `hash` if it was remembered, telling whether it was
+    pub(crate) fn remove(&mut self, hash: u64) -> bool {
+        if self.members.remove(&hash).is_none() {
+            return false;
+        }
+        // stale entries outnumbering live ones pay for one pass over `order`
+        if self.order.len() > 2 * self.members.len() {
+            let members = &self.members;
+            self.order
+                .retain(|(hash, sequence)| members.get(hash) == Some(sequence));
+        }
+        true
+    }
+
+    /// forget the oldest live ghost
+    pub(crate) fn pop(&mut self) -> Option<u64> {
+        while let Some((hash, sequence)) = self.order.pop_front() {
+            if self.members.get(&hash) == Some(&sequence) {
+                self.members.remove(&hash);
+                return Some(hash);
+            }
+        }
+        None
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.order.clear();
+        self.members.clear();
+    }
+}
@@ -1,24 +1,141 @@
-mod ai;
+mod adaptive;
+mod builder;
+mod clock;
+mod entry;
+mod event;
+mod expiry;
+mod flight;
+mod ghost;
+mod listener;
+mod loader;
+mod mirror;
+mod policy;
+mod s3fifo;
+mod sieve;
+mod tinylfu;
+mod wheel;
+mod writer;
 
 use std::borrow::Borrow;
 use std::collections::HashMap;
+use std::collections::HashSet;
+use std::collections::VecDeque;
 use std::fmt::Debug;
 use std::hash::Hash;
+use std::panic::AssertUnwindSafe;
 use std::sync::Arc;
 use std::sync::Mutex;
-use std::time::SystemTime;
-use std::time::UNIX_EPOCH;
+use std::sync::Weak;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use std::time::Instant;
 
-use arc_swap::ArcSwap;
 use crossbeam::channel::Receiver;
 use crossbeam::channel::Sender;
 use crossbeam::channel::bounded;
+use crossbeam::channel::never;
+use crossbeam::channel::tick;
+use crossbeam::channel::unbounded;
+use crossbeam::select;
 use tracing::instrument;
+use tracing::warn;
+
+pub use adaptive::Adaptive;
+pub use builder::Builder;
+pub use clock::Clock;
+#[cfg(any(test, feature = "test-util"))]
+pub use clock::MockClock;
+pub use clock::MonotonicClock;
+pub use entry::Entry;
+pub use event::Event;
+pub use expiry::Expiry;
+pub use listener::EvictionListener;
+pub use listener::RemovalCause;
+pub use loader::CacheLoader;
+pub use loader::Served;
+pub use policy::EvictionPolicy;
+pub use policy::FifoProbation;
+pub use policy::Slots;
+pub use s3fifo::S3Fifo;
+pub use sieve::Sieve;
+pub use writer::CacheWriter;
+pub use writer::MemoryStore;
+pub use writer::WriteMode;
+
+use event::Subscribers;
+use flight::Flight;
+use listener::deliver;
+use mirror::Mirror;
+use policy::Probe;
+use policy::fingerprint;
+use tinylfu::TinyLfu;
+use wheel::TimerWheel;
+use writer::Behind;
+use writer::KeyLocks;
+
+/// period of the daemon sweep over `TimerWheel`
+const TICK: Duration = Duration::from_millis(500);
 
 #[derive(Clone)]
-pub struct Config {
-    pub capacity: usize,
-    pub duration: u64,
+struct Config {
+    capacity: usize,
+    duration: Duration,
+    /// bound of `lazy_tx`
+    bound: usize,
+    /// lower bound of keys `daemon` drains per batch
+    min_batch: usize,
+    /// upper bound of keys `daemon` drains per batch
+    max_batch: usize,
+    /// number of batches the throughput SMA averages over
+    window: usize,
+    /// lifetime of an entry counted from its last read or write
+    idle: Option<Duration>,
+    /// age past which a read schedule a reload through the `CacheLoader`
+    refresh: Option<Duration>,
+    /// how long an outdated node is kept to be served when its reload fail
+    grace: Option<Duration>,
+    /// contest every victim with the `TinyLfu` frequency of the key replacing it
+    admission: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            capacity: 100,
+            duration: Duration::from_secs(5),
+            bound: 10_000,
+            min_batch: 64,
+            max_batch: 4096,
+            window: 5,
+            idle: None,
+            refresh: None,
+            grace: None,
+            admission: false,
+        }
+    }
+}
+
+/// pluggable collaborators handed over by `Builder`
+struct Hooks<K, V> {
+    loader: Option<Arc<dyn CacheLoader<K, V>>>,
+    writer: Option<(Arc<dyn CacheWriter<K, V>>, WriteMode)>,
+    /// moved into `Cache` on build, it decide node lifetimes under `main`
+    expiry: Option<Arc<dyn Expiry<K, V>>>,
+    /// moved into `Cache` on build, `MonotonicClock` unless set
+    clock: Option<Arc<dyn Clock>>,
+    listener: Option<Arc<dyn EvictionListener<K, V>>>,
+}
+
+impl<K, V> Default for Hooks<K, V> {
+    fn default() -> Self {
+        Self {
+            loader: None,
+            writer: None,
+            expiry: None,
+            clock: None,
+            listener: None,
+        }
+    }
 }
 
 #[doc = r#"
@@ -32,34 +149,64 @@ let cache = DualCacheFF::new();
 cache.put("A", 100);
 cache.put("B", 200);
 
-assert_eq!(cache.get("A"), Some(100);
+assert_eq!(cache.get("A"), Some(100));
 assert!(cache.get("C").is_none());
 ```
 
-## Fn `build` customize capacity
+## Fn `builder` customize capacity
 
 ```
-use dual_cache_ff::{DualCacheFF, Config};
-
-let config = Config {
-    capacity: 100, duration: 5000,
-};
-let (cache,rx) = DualCacheFF::build(config);
-thread::spawn(|| {
-   cache.daemon(rx);
- });
+use dual_cache_ff::DualCacheFF;
+
+let (cache, daemon) = DualCacheFF::builder().capacity(100).build().unwrap();
 for i in 0..100 {
     cache.put(i, format!("value_{}", i));
 }
 
-assert!(cache.get(0).is_some());
-assert!(cache.get(99).is_some());
+assert!(cache.get(&0).is_some());
+assert!(cache.get(&99).is_some());
+daemon.shutdown().unwrap();
 ```"#]
 #[repr(align(128))]
-pub struct DualCacheFF<K, V> {
-    main: Mutex<Cache<K, V>>,
-    mirror: ArcSwap<Cache<K, V>>,
+pub struct DualCacheFF<K, V, P = FifoProbation> {
+    main: Mutex<Cache<K, V, P>>,
+    mirror: Mirror<K, V>,
     lazy_tx: Sender<K>,
+    lazy_rx: Receiver<K>,
+    behind_tx: Sender<(K, Option<V>)>,
+    behind_rx: Receiver<(K, Option<V>)>,
+    refresh_tx: Sender<K>,
+    refresh_rx: Receiver<K>,
+    removal_rx: Receiver<(K, V, RemovalCause)>,
+    behind: Option<Arc<Behind<K, V>>>,
+    flights: Mutex<HashMap<K, Arc<Flight<V>>>>,
+    keys: KeyLocks,
+    hooks: Hooks<K, V>,
+}
+
+/// what the daemon keep to flush the queues once the last `Arc<DualCacheFF>` dropped
+struct Leftover<K, V> {
+    behind: Option<Arc<Behind<K, V>>>,
+    listener: Option<Arc<dyn EvictionListener<K, V>>>,
+}
+
+impl<K: Hash + Eq + Clone, V> Leftover<K, V> {
+    /// write the changes and notify the removals nobody else will drain anymore
+    fn drain(
+        &self,
+        changes: impl Iterator<Item = (K, Option<V>)>,
+        removals: impl Iterator<Item = (K, V, RemovalCause)>,
+    ) {
+        if let Some(behind) = &self.behind {
+            behind.flush(changes);
+            if behind.pending() > 0 {
+                warn!(lost = behind.pending(), "write-behind changes lost on drop");
+            }
+        }
+        if let Some(listener) = &self.listener {
+            deliver(listener.as_ref(), removals);
+        }
+    }
 }
 
 impl<K, V> DualCacheFF<K, V>
@@ -68,39 +215,152 @@ where
     V: Clone + Send + Sync + 'static + Debug,
 {
     pub fn new() -> Arc<Self> {
-        let config = Config {
-            capacity: 100,
-            duration: 5, // second
+        Self::builder().build().expect("default builder is valid").0
+    }
+
+    /// builder of a `FifoProbation` cache, `Builder::policy` switch to another `EvictionPolicy`
+    pub fn builder() -> Builder<K, V> {
+        Builder::default()
+    }
+}
+
+impl<K, V, P> DualCacheFF<K, V, P>
+where
+    K: Hash + Eq + Clone + Send + Sync + 'static + Debug,
+    V: Clone + Send + Sync + 'static + Debug,
+    P: EvictionPolicy,
+{
+    #[doc = r#"
+    # Feature
+    - **Daemon handle**
+    - **Drop shutdown**
+
+    # Example
+    ## Daemon handle
+    `Daemon::shutdown` flush pending promotions, publish final `mirror` and join the thread
+    ```
+    use dual_cache_ff::DualCacheFF;
+
+    let (cache, daemon) = DualCacheFF::builder().build().unwrap();
+    cache.put("A", 100);
+    cache.get("A");
+    daemon.shutdown().unwrap();
+
+    assert_eq!(cache.get("A"), Some(100));
+    ```
+
+    ## Drop shutdown
+    `daemon` only hold `Weak`, once the last `Arc` drop `lazy_tx` disconnect and the thread exit
+    ```
+    use dual_cache_ff::DualCacheFF;
+
+    let (cache, daemon) = DualCacheFF::<u32, u32>::builder().build().unwrap();
+    drop(cache);
+    daemon.join().unwrap();
+    ```
+    "#]
+    fn spawn(
+        config: Config,
+        hooks: Hooks<K, V>,
+        name: String,
+    ) -> std::io::Result<(Arc<Self>, Daemon)> {
+        let cache = DualCacheFF::build(config, hooks);
+        let rx = cache.lazy_rx.clone();
+        let behind_rx = cache.behind_rx.clone();
+        let refresh_rx = cache.refresh_rx.clone();
+        let removal_rx = cache.removal_rx.clone();
+        let leftover = Leftover {
+            behind: cache.behind.clone(),
+            listener: cache.hooks.listener.clone(),
         };
-        let (cache, rx) = DualCacheFF::build(config);
-        let cache_clone = cache.clone();
-        std::thread::spawn(move || {
-            cache_clone.daemon(rx);
-        });
-        cache    
+        let (stop_tx, stop_rx) = bounded(1);
+        let weak = Arc::downgrade(&cache);
+        let handle = std::thread::Builder::new().name(name).spawn(move || {
+            Self::daemon(
+                weak, rx, behind_rx, refresh_rx, removal_rx, stop_rx, leftover,
+            )
+        })?;
+
+        Ok((
+            cache,
+            Daemon {
+                stop: stop_tx,
+                handle,
+            },
+        ))
+    }
+
+    #[doc = r#"
+    # Feature
+    - **No thread**
+    - **Run on demand**
+
+    # Example
+    ## No thread
+    nothing spawned, promotions wait in `lazy_tx` until the caller drive them
+    ## Run on demand
+    `run_pending_tasks` drain `lazy_tx` and republish `mirror`, with no daemon to flush them
+    write-behind changes still queued when the last `Arc` drop are lost
+    ```
+    use dual_cache_ff::DualCacheFF;
+
+    let cache = DualCacheFF::builder().build_manual().unwrap();
+    cache.put("A", 100);
+    assert_eq!(cache.get("A"), Some(100));
+
+    cache.run_pending_tasks();
+    ```
+    "#]
+    fn manual(config: Config, hooks: Hooks<K, V>) -> Arc<Self> {
+        DualCacheFF::build(config, hooks)
     }
 
-    fn build(config: Config) -> (Arc<Self>, Receiver<K>) {
+    fn build(config: Config, mut hooks: Hooks<K, V>) -> Arc<Self> {
         let capacity = config.capacity;
+        let clock = hooks
+            .clock
+            .take()
+            .unwrap_or_else(|| Arc::new(MonotonicClock::new()));
+        let (removal_tx, removal_rx) = unbounded();
+        let mirror = Mirror::new(clock.clone(), config.clone());
         let state = Cache {
             nodes: Vec::with_capacity(capacity),
             index: HashMap::with_capacity(capacity),
-            arena: Vec::with_capacity(capacity),
-            evict_point: 0,
+            vacant: Vec::new(),
             lookup_count: 0,
-            ring_pointer: 0,
+            policy: P::with_capacity(capacity),
+            admission: config.admission.then(|| TinyLfu::new(capacity)),
+            wheel: TimerWheel::new(capacity, clock.now()),
+            expiry: hooks.expiry.take(),
+            clock,
+            removal_tx: hooks.listener.is_some().then_some(removal_tx),
+            subscribers: Subscribers::default(),
+            dirty: HashSet::new(),
             config,
         };
-        let (lazy_tx, rx) = bounded(10_000);
-
-        (
-            Arc::new(Self {
-                mirror: ArcSwap::from_pointee(state.clone()),
-                main: Mutex::new(state),
-                lazy_tx,
-            }),
-            rx,
-        )
+        let (lazy_tx, lazy_rx) = bounded(state.config.bound);
+        let (behind_tx, behind_rx) = unbounded();
+        let (refresh_tx, refresh_rx) = bounded(state.config.bound);
+        let behind = match &hooks.writer {
+            Some((writer, WriteMode::Behind)) => Some(Arc::new(Behind::new(writer.clone()))),
+            _ => None,
+        };
+
+        Arc::new(Self {
+            mirror,
+            main: Mutex::new(state),
+            lazy_tx,
+            lazy_rx,
+            behind_tx,
+            behind_rx,
+            refresh_tx,
+            refresh_rx,
+            removal_rx,
+            behind,
+            flights: Mutex::new(HashMap::new()),
+            keys: KeyLocks::new(),
+            hooks,
+        })
     }
 
     #[doc = r#"
@@ -115,13 +375,34 @@ where
     ```
     use dual_cache_ff::DualCacheFF;
 
+    let cache = DualCacheFF::new();
+    for i in 0..=100 {
+        cache.put(i, i);
+    }
+
+    assert!(cache.get(&0).is_none());
+    assert_eq!(cache.get(&1), Some(1));
+    assert_eq!(cache.get(&100), Some(100));
     ```
 
     ## Arena evict probation
-    struct `cache` field `evict_ponit` index above 
+    struct `cache` field `evict_point` index above, `arena` rank below `evict_point` skip by `ring_pointer`
     ```
     use dual_cache_ff::DualCacheFF;
 
+    let cache = DualCacheFF::builder().build_manual().unwrap();
+    for i in 0..100 {
+        cache.put(i, i);
+    }
+    for _ in 0..10 {
+        cache.get(&7);
+        cache.run_pending_tasks();
+    }
+    for i in 100..200 {
+        cache.put(i, i);
+    }
+
+    assert_eq!(cache.get(&7), Some(7));
     ```
 
     ## Count evict probation
@@ -129,11 +410,226 @@ where
     ```
     use dual_cache_ff::DualCacheFF;
 
+    let cache = DualCacheFF::builder().build_manual().unwrap();
+    for i in 0..100 {
+        cache.put(i, i);
+    }
+    cache.get(&0);
+    cache.run_pending_tasks();
+    cache.put(100, 100);
+
+    assert_eq!(cache.get(&0), Some(0));
+    assert!(cache.get(&1).is_none());
     ```
     "#]
     #[instrument(skip(self, value), fields(key = ?key))]
     pub fn put(&self, key: K, value: V) {
-        AI!()
+        if let Err(err) = self.try_put(key, value) {
+            warn!(%err, "write-through failed, value not cached");
+        }
+    }
+
+    /// `put` that surface a write-through failure instead of logging it, the value is only
+    /// cached once the `CacheWriter` accepted it
+    pub fn try_put(&self, key: K, value: V) -> anyhow::Result<()> {
+        self.write(key, value, None)
+    }
+
+    #[doc = r#"
+    # Feature
+    - **Per node ttl**
+
+    # Example
+    ## Per node ttl
+    `node.ttl` override `config.duration` for this write only, checked by `get` and preferred
+    by `ring_pointer` as victim once outdated
+    ```
+    use std::time::Duration;
+    use dual_cache_ff::DualCacheFF;
+
+    let cache = DualCacheFF::new();
+    cache.put_with_ttl("token", 1, Duration::ZERO);
+    cache.put_with_ttl("session", 2, Duration::from_secs(3_600));
+
+    assert!(cache.get("token").is_none());
+    assert_eq!(cache.get("session"), Some(2));
+    ```
+    "#]
+    #[instrument(skip(self, value), fields(key = ?key))]
+    pub fn put_with_ttl(&self, key: K, value: V, ttl: Duration) {
+        if let Err(err) = self.write(key, value, Some(ttl)) {
+            warn!(%err, "write-through failed, value not cached");
+        }
+    }
+
+    /// propagate then insert under the lock of `key`, so the `CacheWriter` see the changes of a
+    /// key in the order the cache applied them while other keys go on
+    fn write(&self, key: K, value: V, ttl: Option<Duration>) -> anyhow::Result<()> {
+        let _key = self.keys.lock(&key);
+        self.propagate(&key, Some(&value))?;
+        let mut state = self.main.lock().unwrap();
+        let epoch = state.clock.now();
+        state.insert(key, value, epoch, ttl);
+        self.mirror.publish(&mut state);
+        Ok(())
+    }
+
+    /// write into the cache alone, for values that came from the backing store
+    fn fill(&self, key: K, value: V) {
+        let mut state = self.main.lock().unwrap();
+        let epoch = state.clock.now();
+        state.insert(key, value, epoch, None);
+        self.mirror.publish(&mut state);
+    }
+
+    /// lookup and insert `key` atomically under the lock of `key`, see `Entry`
+    pub fn entry(&self, key: K) -> Entry<'_, K, V, P> {
+        Entry::new(self, key)
+    }
+
+    #[doc = r#"
+    # Feature
+    - **Slot vacate**
+    - **Arena demote**
+
+    - **Delete propagate**
+
+    # Example
+    ## Slot vacate
+    drop the node from `index` and `nodes`, the slot wait in `vacant` for the next `put`
+    before `ring_pointer` overwrite anything
+    ```
+    use dual_cache_ff::DualCacheFF;
+
+    let cache = DualCacheFF::new();
+    cache.put("A", 100);
+
+    assert_eq!(cache.remove("A"), Some(100));
+    assert_eq!(cache.remove("A"), None);
+    assert!(cache.get("A").is_none());
+    ```
+
+    ## Arena demote
+    the vacated slot move to the tail of `arena`, `evict_point` shrink if it was protected
+
+    ## Delete propagate
+    a configured `CacheWriter` is asked to `delete` the key, `invalidate` only drop the cached copy
+    "#]
+    #[instrument(skip(self), fields(key = ?key))]
+    pub fn remove<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized + Debug,
+    {
+        let _key = self.keys.lock(key);
+        let node = {
+            let mut state = self.main.lock().unwrap();
+            let node = state.remove(key)?;
+            self.mirror.publish(&mut state);
+            node
+        };
+        if let Err(err) = self.propagate(&node.key, None) {
+            warn!(%err, "write-through delete failed");
+        }
+
+        Some(node.value)
+    }
+
+    /// drop the cached copy of `key`, leaving any `CacheWriter` untouched
+    pub fn invalidate<Q>(&self, key: &Q)
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized + Debug,
+    {
+        let mut state = self.main.lock().unwrap();
+        if state.remove(key).is_some() {
+            self.mirror.publish(&mut state);
+        }
+    }
+
+    #[doc = r#"
+    # Feature
+    - **Predicate scan**
+    - **Single publish**
+    - **Predicate panic**
+
+    # Example
+    ## Predicate scan
+    walk `nodes` under `main`, vacate every node `predicate` accept and return how many
+    ```
+    use dual_cache_ff::DualCacheFF;
+
+    let cache = DualCacheFF::new();
+    cache.put("tenant-a/1", 1);
+    cache.put("tenant-a/2", 2);
+    cache.put("tenant-b/1", 3);
+
+    assert_eq!(cache.invalidate_if(|key, _| key.starts_with("tenant-a/")), 2);
+    assert!(cache.get("tenant-a/1").is_none());
+    assert_eq!(cache.get("tenant-b/1"), Some(3));
+    ```
+
+    ## Single publish
+    vacated slots sink to the tail of `arena` in one pass and `mirror` is stored once
+
+    ## Predicate panic
+    every node is matched before any is vacated, a panicking `predicate` leave the cache
+    untouched and unwind to the caller after `main` is released, so the lock is not poisoned
+    "#]
+    pub fn invalidate_if(&self, predicate: impl FnMut(&K, &V) -> bool) -> usize {
+        let mut state = self.main.lock().unwrap();
+        let removed = std::panic::catch_unwind(AssertUnwindSafe(|| state.remove_if(predicate)));
+        let removed = match removed {
+            Ok(removed) => removed,
+            Err(panic) => {
+                drop(state);
+                std::panic::resume_unwind(panic);
+            }
+        };
+        self.mirror.publish(&mut state);
+
+        removed
+    }
+
+    #[doc = r#"
+    # Feature
+    - **Drop every node**
+    - **Keep traffic**
+
+    # Example
+    ## Drop every node
+    empty `nodes`, `index` and `arena`, rewind `ring_pointer` and `evict_point`
+    ```
+    use dual_cache_ff::DualCacheFF;
+
+    let cache = DualCacheFF::new();
+    cache.put("A", 100);
+    cache.put("B", 200);
+    cache.invalidate_all();
+
+    assert!(cache.get("A").is_none());
+    assert!(cache.get("B").is_none());
+    ```
+
+    ## Keep traffic
+    `lookup_count` survive, new nodes still have to beat the learned average to be protected,
+    `clear` forget it as well
+    "#]
+    pub fn invalidate_all(&self) {
+        let mut state = self.main.lock().unwrap();
+        state.clear();
+        self.mirror.publish(&mut state);
+    }
+
+    /// reset to a freshly built cache, dropping every node and the learned `lookup_count`
+    pub fn clear(&self) {
+        let mut state = self.main.lock().unwrap();
+        state.clear();
+        state.lookup_count = 0;
+        if let Some(admission) = &mut state.admission {
+            admission.clear();
+        }
+        self.mirror.publish(&mut state);
     }
 
     #[doc = r#"
@@ -145,28 +641,49 @@ where
 
     # Example 
     ## Outdated check
-    check (`SystemTime::now()` - `node.epoch`) < `cache.epoch_duration`
-    ```
+    check (`Clock::now` - `node.epoch`) < `config.duration`
+    ```no_run
+    use std::{thread, time::Duration};
     use dual_cache_ff::DualCacheFF;
 
+    let cache = DualCacheFF::new();
+    cache.put("A", 100);
+    assert_eq!(cache.get("A"), Some(100));
+
+    thread::sleep(Duration::from_secs(5));
+    assert!(cache.get("A").is_none());
     ```
     ## Count progress
-    `node.count` +1 every call
+    `node.count` +1 every call, applied by `daemon` through `lazy_tx`
     ```
     use dual_cache_ff::DualCacheFF;
 
+    let cache = DualCacheFF::new();
+    cache.put("A", 100);
+    for _ in 0..3 {
+        assert_eq!(cache.get("A"), Some(100));
+    }
     ```
     ## Arena progress
-    `pagenated.page` move `node` index forward every call
+    `arena` move `node` index forward every call
     ```
     use dual_cache_ff::DualCacheFF;
 
+    let cache = DualCacheFF::new();
+    cache.put("A", 100);
+    cache.put("B", 200);
+    assert_eq!(cache.get("B"), Some(200));
     ```
     ## Count rest
     beyond (cache.lookup_count/cache.capacity) *10  node count will freeze   
     ```
     use dual_cache_ff::DualCacheFF;
 
+    let cache = DualCacheFF::new();
+    cache.put("A", 100);
+    for _ in 0..1_000 {
+        cache.get("A");
+    }
     ```  
     "#]
     #[instrument(skip(self), fields(key = ?key))]
@@ -175,118 +692,1770 @@ where
         K: Borrow<Q>,
         Q: Hash + Eq + ?Sized + Debug,
     {
-        AI!()
+        self.hit(key)
+    }
+
+    /// `get` served by `mirror` alone, queuing the promotion and any refresh
+    fn hit<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let now = self.mirror.clock.now();
+        let node = self.mirror.lookup(key, now)?;
+        // promotion is only a hint, drop it rather than block the reader
+        let _ = self.lazy_tx.try_send(node.key.clone());
+        if node.stale(now, self.mirror.config.refresh) {
+            let _ = self.refresh_tx.try_send(node.key.clone());
+        }
+
+        Some(node.value.clone())
+    }
+
+    #[doc = r#"
+    # Feature
+    - **Adaptive batch**
+    - **Mirror publish**
+    - **Stop signal**
+    - **Write behind**
+    - **Drop flush**
+    - **Expiration**
+    - **Refresh ahead**
+    - **Eviction listener**
+
+    # Example
+    ## Adaptive batch
+    drain `lazy_tx` up to `batch.size` keys per lock, the SMA of keys drained per second is
+    projected over one `TICK`, `size` double when that fill three quarters of it and halve when
+    below a quarter, clamp in `min_batch`..=`max_batch`
+
+    ## Mirror publish
+    `climb`, `refresh`, `calibrate` once per batch then the shards of `mirror` holding the
+    promoted keys are stored
+
+    ## Stop signal
+    `Daemon::shutdown` drain whatever left in `lazy_tx` and publish once more, a dropped
+    `Daemon` only detach the thread
+
+    ## Write behind
+    queued `CacheWriter` changes are drained in the same batch size, coalesced per key, a
+    failed batch is retried ahead of the next one or on the next `TICK`, a panicking one is
+    logged and dropped
+
+    ## Drop flush
+    once the last `Arc` dropped the daemon hand the changes and removals still queued to its own
+    `Leftover` writer and listener before it exit, nothing `put` is lost to the drop
+
+    ## Expiration
+    every `TICK` the `TimerWheel` is advanced and due nodes are vacated, so an outdated entry
+    free its slot without waiting for a `victim` walk or a `get`
+
+    ## Refresh ahead
+    keys `get` found older than `config.refresh` are reloaded through the `CacheLoader` here,
+    outside `main`, so a slow loader delay promotions but never a reader, a panicking loader is
+    logged and the key keep its value
+
+    ## Eviction listener
+    removals queued under `main` are handed to the `EvictionListener` here, never on the
+    thread that caused them
+    "#]
+    fn daemon(
+        cache: Weak<Self>,
+        rx: Receiver<K>,
+        behind_rx: Receiver<(K, Option<V>)>,
+        refresh_rx: Receiver<K>,
+        removal_rx: Receiver<(K, V, RemovalCause)>,
+        stop: Receiver<()>,
+        leftover: Leftover<K, V>,
+    ) {
+        let Some(mut batch) = cache
+            .upgrade()
+            .map(|cache| Batch::new(&cache.main.lock().unwrap().config))
+        else {
+            leftover.drain(behind_rx.try_iter(), removal_rx.try_iter());
+            return;
+        };
+        let mut stop = stop;
+        let mut removal_rx = removal_rx;
+        let ticker = tick(TICK);
+        // received before the upgrade failed, flushed ahead of the rest of its queue
+        let mut held_change = None;
+        let mut held_removal = None;
+
+        'run: loop {
+            select! {
+                recv(rx) -> first_key => {
+                    let (Ok(first_key), Some(cache)) = (first_key, cache.upgrade()) else {
+                        break 'run;
+                    };
+                    let keys = std::iter::once(first_key).chain(rx.try_iter().take(batch.size - 1));
+                    let elapsed = batch.lap();
+                    batch.adjust(cache.promote(keys), elapsed);
+                }
+                recv(behind_rx) -> first_change => {
+                    let Ok(first_change) = first_change else {
+                        break 'run;
+                    };
+                    let Some(cache) = cache.upgrade() else {
+                        held_change = Some(first_change);
+                        break 'run;
+                    };
+                    let changes = std::iter::once(first_change)
+                        .chain(behind_rx.try_iter().take(batch.size - 1));
+                    cache.write_behind(changes);
+                }
+                recv(refresh_rx) -> first_key => {
+                    let (Ok(first_key), Some(cache)) = (first_key, cache.upgrade()) else {
+                        break 'run;
+                    };
+                    let keys = std::iter::once(first_key)
+                        .chain(refresh_rx.try_iter().take(batch.size - 1));
+                    cache.reload(keys);
+                }
+                recv(removal_rx) -> first_removal => {
+                    // without a listener no `Cache` hold the sender
+                    let Ok(first_removal) = first_removal else {
+                        removal_rx = never();
+                        continue;
+                    };
+                    let Some(cache) = cache.upgrade() else {
+                        held_removal = Some(first_removal);
+                        break 'run;
+                    };
+                    let removals = std::iter::once(first_removal)
+                        .chain(removal_rx.try_iter().take(batch.size - 1));
+                    cache.notify(removals);
+                }
+                recv(ticker) -> _ => {
+                    let Some(cache) = cache.upgrade() else {
+                        break 'run;
+                    };
+                    cache.reclaim();
+                    cache.write_behind(std::iter::empty());
+                }
+                recv(stop) -> signal => {
+                    if signal.is_err() {
+                        stop = never();
+                        continue;
+                    }
+                    if let Some(cache) = cache.upgrade() {
+                        cache.run_pending_tasks();
+                        return;
+                    }
+                    break 'run;
+                }
+            }
+        }
+
+        leftover.drain(
+            held_change.into_iter().chain(behind_rx.try_iter()),
+            held_removal.into_iter().chain(removal_rx.try_iter()),
+        );
     }
 
-    pub fn daemon(&self, rx: Receiver<K>) {
-        // let mut batch_size: u64 = 1024;
-        // let mut sma: VecDeque<u64> = VecDeque::with_capacity(5);
+    /// process every promotion queued on `lazy_tx`, vacate expired nodes, republish `mirror`,
+    /// run scheduled refreshes, notify removals and flush pending write-behind changes
+    pub fn run_pending_tasks(&self) {
+        self.promote(self.lazy_rx.try_iter());
+        self.reload(self.refresh_rx.try_iter());
+        self.notify(self.removal_rx.try_iter());
+        self.write_behind(self.behind_rx.try_iter());
+    }
+
+    /// apply one batch of promotions under `main` and publish it to `mirror`
+    fn promote(&self, keys: impl Iterator<Item = K>) -> usize {
+        let mut state = self.main.lock().unwrap();
+        let mut processed_count = 0;
+        let now = state.clock.now();
+
+        state.apply(keys.inspect(|_| processed_count += 1), now);
+        if state.lookup_count > u64::MAX / 2 {
+            state.refresh();
+        }
+        state.calibrate();
+        state.expire(now);
+
+        self.mirror.publish(&mut state);
+        processed_count
+    }
 
-        // while let Ok(first_key) = rx.recv() {
-        //     let mut state = self.main.lock().unwrap();
-        //     let mut processed_count = 0;
-        //     let start_epoch = SystemTime::now();
+    /// vacate nodes the `TimerWheel` found due, `mirror` is only touched if any was
+    fn reclaim(&self) {
+        let mut state = self.main.lock().unwrap();
+        let now = state.clock.now();
+        state.expire(now);
+        self.mirror.publish(&mut state);
+    }
+}
 
-        //     // 將 K 轉換為 usize index 才能 climb
-        //     if let Some(&idx) = state.index.get(&first_key) {
-        //         state.climb(idx);
-        //     }
-        //     processed_count += 1;
+/// owned handle of the thread running `daemon`
+pub struct Daemon {
+    stop: Sender<()>,
+    handle: JoinHandle<()>,
+}
 
-        //     while processed_count < batch_size {
-        //         match rx.try_recv() {
-        //             Ok(key) => {
-        //                 if let Some(&idx) = state.index.get(&key) {
-        //                     state.climb(idx);
-        //                 }
-        //                 processed_count += 1;
-        //             }
-        //             Err(_) => break,
-        //         }
-        //     }
+impl Daemon {
+    /// signal the daemon to flush pending promotions, publish a final mirror and join it
+    pub fn shutdown(self) -> std::thread::Result<()> {
+        // an exited daemon already dropped its receiver
+        let _ = self.stop.send(());
+        self.handle.join()
+    }
 
-        //     state.lookup_count = state.lookup_count.saturating_add(processed_count as u64);
+    /// wait for the daemon to exit on its own, after the last `Arc<DualCacheFF>` dropped
+    pub fn join(self) -> std::thread::Result<()> {
+        self.handle.join()
+    }
+}
 
-        //     if state.lookup_count > u64::MAX / 2 {
-        //         state.refresh();
-        //     }
-        //     state.calibrate();
+/// batch size of `daemon`, adjusted by simple moving average of keys drained per second
+struct Batch {
+    size: usize,
+    sma: VecDeque<f64>,
+    min: usize,
+    max: usize,
+    window: usize,
+    /// start of the previous batch
+    last: Instant,
+}
 
-        //     let new_snapshot = Arc::new(state.clone());
-        //     self.mirror.store(new_snapshot);
+impl Batch {
+    fn new(config: &Config) -> Self {
+        Self {
+            size: config.min_batch,
+            sma: VecDeque::with_capacity(config.window),
+            min: config.min_batch,
+            max: config.max_batch,
+            window: config.window,
+            last: Instant::now(),
+        }
+    }
 
-        //     if sma.len() == 5 {
-        //         sma.pop_front();
-        //     }
+    /// time since the previous batch started, this one start now
+    fn lap(&mut self) -> Duration {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last);
+        self.last = now;
+        elapsed
+    }
 
-        //     // 避免除以 0 的情況
-        //     let elapsed_ms = start_epoch.elapsed().unwrap_or_default().as_secs().max(1);
-        //     sma.push_back(batch_size / elapsed_ms);
+    fn adjust(&mut self, processed_count: usize, elapsed: Duration) {
+        if self.sma.len() == self.window {
+            self.sma.pop_front();
+        }
+        // a batch right after the previous one would read as infinite throughput
+        let elapsed = elapsed.max(Duration::from_micros(1));
+        self.sma
+            .push_back(processed_count as f64 / elapsed.as_secs_f64());
+        let throughput = self.sma.iter().sum::<f64>() / self.sma.len() as f64;
+        let expected = throughput * TICK.as_secs_f64();
 
-        //     todo!("adjust BATCH_SIZE with SMA if intense batch bigger vise versa")
-        // }
+        if expected * 4.0 >= self.size as f64 * 3.0 {
+            self.size = self.size.saturating_mul(2).min(self.max);
+        } else if expected * 4.0 < self.size as f64 {
+            self.size = (self.size / 2).max(self.min);
+        }
     }
 }
 
-#[derive(Clone)]
-struct Cache<K, V> {
-    nodes: Vec<Node<K, V>>,
+struct Cache<K, V, P> {
+    nodes: Vec<Option<Node<K, V>>>,
     index: HashMap<K, usize>,
-    arena: Vec<usize>,
-    evict_point: usize,
+    vacant: Vec<usize>,
     lookup_count: u64,
-    ring_pointer: usize,
+    policy: P,
+    /// set only by `Builder::tiny_lfu`
+    admission: Option<TinyLfu>,
+    wheel: TimerWheel,
+    expiry: Option<Arc<dyn Expiry<K, V>>>,
+    clock: Arc<dyn Clock>,
+    /// set only with an `EvictionListener`, drained by the daemon
+    removal_tx: Option<Sender<(K, V, RemovalCause)>>,
+    subscribers: Subscribers<K, V>,
+    /// keys whose node changed since `Mirror::publish` last ran
+    dirty: HashSet<K>,
     config: Config,
 }
 
-impl<K, V> Cache<K, V> {
-    fn climb(&mut self, index: usize) {
-        self.arena.swap(index, index - 1);
+impl<K, V, P> Cache<K, V, P> {
+    /// queue a removed node for the `EvictionListener`, if any
+    fn listen(&self, node: Node<K, V>, cause: RemovalCause) {
+        if let Some(removal_tx) = &self.removal_tx {
+            // the receiver lives as long as `DualCacheFF`
+            let _ = removal_tx.send((node.key, node.value, cause));
+        }
+    }
+
+    /// (re)schedule `slot` on the `TimerWheel`, a stale node is kept through `config.grace`
+    fn arm(&mut self, slot: usize) {
+        if let Some(node) = &self.nodes[slot] {
+            let deadline = node.deadline(self.config.idle).saturating_add(self.grace());
+            self.wheel.schedule(slot, deadline);
+        }
+    }
+
+    fn grace(&self) -> Duration {
+        self.config.grace.unwrap_or_default()
+    }
+}
+
+impl<K: Hash, V, P: EvictionPolicy> Cache<K, V, P> {
+    /// hand `policy` a `Slots` view over `nodes` as of `now`
+    fn with_policy<R>(&mut self, now: Duration, f: impl FnOnce(&mut P, &mut Slots<'_>) -> R) -> R {
+        let mut probe = NodeProbe {
+            nodes: &mut self.nodes,
+            now,
+            idle: self.config.idle,
+        };
+        let mut slots = Slots::new(&mut probe, self.config.capacity, self.lookup_count);
+        f(&mut self.policy, &mut slots)
+    }
+
+    fn calibrate(&mut self) {
+        let now = self.clock.now();
+        self.with_policy(now, |policy, slots| policy.on_batch(slots));
     }
+}
 
+impl<K: Hash + Eq + Clone, V, P: EvictionPolicy> Cache<K, V, P> {
     fn refresh(&mut self) {
         self.lookup_count = 0;
-        let epoch = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .expect("somewhat went wrong")
-            .as_secs();
+        let epoch = self.clock.now();
 
-        for nxt in self.nodes.iter_mut() {
+        for nxt in self.nodes.iter_mut().flatten() {
             nxt.count = 0;
             nxt.epoch = epoch;
+            self.dirty.insert(nxt.key.clone());
         }
     }
 
-    fn calibrate(&mut self) {
-        let prev_idx = self.evict_point.saturating_sub(1);
-        let latter_idx = self.evict_point.saturating_add(1);
-        let prev_node = self.nodes.get(prev_idx);
-        let latter_node = self.nodes.get(latter_idx);
-        let capacity = self.config.capacity as u64;
-
-        if let Some(prev) = prev_node {
-            if let Some(latter) = latter_node {
-                if (self.lookup_count /capacity) < prev.count {
-                    self.evict_point = self.evict_point.saturating_sub(1);
-                } else if (self.lookup_count / capacity) < latter.count {
-                    self.evict_point = self.evict_point.saturating_add(1);
+    /// the node of `key` unless it outlived its ttl or sat idle past `config.idle`
+    fn lookup<Q>(&self, key: &Q, now: Duration) -> Option<&Node<K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let node = self.nodes.get(*self.index.get(key)?)?.as_ref()?;
+        (!node.outdated(now, self.config.idle)).then_some(node)
+    }
+
+    fn apply(&mut self, keys: impl IntoIterator<Item = K>, now: Duration) {
+        for key in keys {
+            if let Some(admission) = &mut self.admission {
+                admission.record(fingerprint(&key));
+            }
+            let Some(&slot) = self.index.get(&key) else {
+                continue;
+            };
+            self.lookup_count = self.lookup_count.saturating_add(1);
+            self.dirty.insert(key);
+            if let Some(node) = self.nodes[slot].as_mut() {
+                if let Some(expiry) = &self.expiry {
+                    let remaining = node.remaining(now);
+                    let ttl = expiry::guarded(
+                        "expire_after_read",
+                        || expiry.expire_after_read(&node.key, &node.value, remaining),
+                        remaining,
+                    );
+                    node.ttl = now.saturating_sub(node.epoch).saturating_add(ttl);
                 }
+                node.access = now;
             }
+            // a later deadline is caught up by `expire`, only a shortened one must move
+            if self.expiry.is_some() {
+                self.arm(slot);
+            }
+            self.with_policy(now, |policy, slots| policy.on_access(slot, slots));
         }
     }
+}
 
-    fn next(&mut self)->usize{
-        self.ring_pointer = (self.ring_pointer + 1) % self.config.capacity;
-        self.ring_pointer
+impl<K: Hash + Eq + Clone, V: Clone, P: EvictionPolicy> Cache<K, V, P> {
+    /// write `key` in place if present, otherwise into a vacant slot, a fresh slot or the
+    /// `victim` picked by `policy`, unless `admission` find the victim more popular
+    fn insert(&mut self, key: K, value: V, epoch: Duration, ttl: Option<Duration>) {
+        if let Some(&slot) = self.index.get(&key)
+            && self.nodes[slot].is_some()
+        {
+            self.renew(slot, epoch, ttl, |slot_value| *slot_value = value);
+            return;
+        }
+        let candidate = fingerprint(&key);
+        if let Some(admission) = &mut self.admission {
+            admission.record(candidate);
+        }
+        let ttl = ttl.unwrap_or_else(|| match &self.expiry {
+            Some(expiry) => expiry::guarded(
+                "expire_after_create",
+                || expiry.expire_after_create(&key, &value),
+                self.config.duration,
+            ),
+            None => self.config.duration,
+        });
+        let node = Some(Node {
+            key: key.clone(),
+            value,
+            epoch,
+            access: epoch,
+            ttl,
+            count: 0,
+        });
+        let slot = if let Some(slot) = self.vacant.pop() {
+            self.nodes[slot] = node;
+            slot
+        } else if self.nodes.len() < self.config.capacity {
+            self.nodes.push(node);
+            self.nodes.len() - 1
+        } else {
+            let (slot, cause) =
+                self.with_policy(epoch, |policy, slots| policy.victim(candidate, slots));
+            if cause != RemovalCause::Expired
+                && let Some(admission) = &self.admission
+                && let Some(victim) = &self.nodes[slot]
+                && !admission.admit(candidate, fingerprint(&victim.key))
+            {
+                // the victim keep its node and go back where `victim` took it from
+                self.with_policy(epoch, |policy, slots| policy.on_reject(slot, slots));
+                return;
+            }
+            if let Some(old) = std::mem::replace(&mut self.nodes[slot], node) {
+                self.index.remove(&old.key);
+                self.dirty.insert(old.key.clone());
+                self.notify(old, cause);
+            }
+            slot
+        };
+        self.dirty.insert(key.clone());
+        self.index.insert(key, slot);
+        self.arm(slot);
+        self.with_policy(epoch, |policy, slots| policy.on_insert(slot, slots));
+        if let Some(node) = &self.nodes[slot] {
+            event::publish(&mut self.subscribers, || {
+                Event::Insert(node.key.clone(), node.value.clone())
+            });
+        }
     }
-    fn apply(&mut self,keys: impl IntoIterator<Item = K>){}
-}
 
-#[repr(align(128))]
-#[derive(Clone)]
-struct Node<K, V> {
-    key: K,
-    value: V,
-    epoch: u64,
-    count: u64,
-}
+    /// rewrite the value of an occupied slot, its lifetime restart from `epoch`
+    fn renew(
+        &mut self,
+        slot: usize,
+        epoch: Duration,
+        ttl: Option<Duration>,
+        write: impl FnOnce(&mut V),
+    ) {
+        let Some(node) = self.nodes[slot].as_mut() else {
+            return;
+        };
+        let remaining = node.remaining(epoch);
+        let replaced = self.removal_tx.is_some().then(|| {
+            let cause = match node.outdated(epoch, self.config.idle) {
+                true => RemovalCause::Expired,
+                false => RemovalCause::Replaced,
+            };
+            (node.clone(), cause)
+        });
+        write(&mut node.value);
+        node.ttl = ttl.unwrap_or_else(|| match &self.expiry {
+            Some(expiry) => expiry::guarded(
+                "expire_after_update",
+                || expiry.expire_after_update(&node.key, &node.value, remaining),
+                self.config.duration,
+            ),
+            None => self.config.duration,
+        });
+        node.epoch = epoch;
+        node.access = epoch;
+        self.dirty.insert(node.key.clone());
+        self.arm(slot);
+        if let Some(node) = &self.nodes[slot] {
+            event::publish(&mut self.subscribers, || {
+                Event::Update(node.key.clone(), node.value.clone())
+            });
+        }
+        if let Some((old, cause)) = replaced {
+            self.listen(old, cause);
+        }
+    }
+
+    fn remove<Q>(&mut self, key: &Q) -> Option<Node<K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let slot = self.index.remove(key)?;
+        self.vacant.push(slot);
+        self.wheel.cancel(slot);
+        let node = self.nodes[slot].take()?;
+        self.dirty.insert(node.key.clone());
+        self.vacated(&[slot]);
+        event::publish(&mut self.subscribers, || {
+            Event::Remove(node.key.clone(), node.value.clone())
+        });
+        if self.removal_tx.is_some() {
+            self.listen(node.clone(), RemovalCause::Explicit);
+        }
+        Some(node)
+    }
+
+    fn remove_if(&mut self, mut predicate: impl FnMut(&K, &V) -> bool) -> usize {
+        // matched first, a panicking `predicate` leave every node in place
+        let vacated: Vec<usize> = (0..self.nodes.len())
+            .filter(|&slot| {
+                self.nodes[slot]
+                    .as_ref()
+                    .is_some_and(|node| predicate(&node.key, &node.value))
+            })
+            .collect();
+        if vacated.is_empty() {
+            return 0;
+        }
+        let mut removed = Vec::with_capacity(vacated.len());
+        for &slot in &vacated {
+            removed.extend(self.nodes[slot].take());
+            self.wheel.cancel(slot);
+        }
+        self.vacant.extend_from_slice(&vacated);
+        self.vacated(&vacated);
+        for node in removed {
+            self.index.remove(&node.key);
+            self.dirty.insert(node.key.clone());
+            self.notify(node, RemovalCause::Explicit);
+        }
+
+        vacated.len()
+    }
+
+    /// vacate every node the `TimerWheel` swept up to `now` that is outdated past
+    /// `config.grace`, the rest are rearmed
+    fn expire(&mut self, now: Duration) -> usize {
+        let mut vacated = Vec::new();
+        for slot in self.wheel.advance(now) {
+            let Some(node) = self.nodes[slot].as_ref() else {
+                continue;
+            };
+            if !node.outdated(now.saturating_sub(self.grace()), self.config.idle) {
+                self.arm(slot);
+                continue;
+            }
+            if let Some(node) = self.nodes[slot].take() {
+                self.index.remove(&node.key);
+                self.dirty.insert(node.key.clone());
+                self.vacant.push(slot);
+                self.notify(node, RemovalCause::Expired);
+                vacated.push(slot);
+            }
+        }
+        if !vacated.is_empty() {
+            self.vacated(&vacated);
+        }
+        vacated.len()
+    }
+
+    fn clear(&mut self) {
+        for node in std::mem::take(&mut self.nodes).into_iter().flatten() {
+            self.dirty.insert(node.key.clone());
+            self.notify(node, RemovalCause::Explicit);
+        }
+        self.index.clear();
+        self.vacant.clear();
+        self.wheel.clear();
+        self.policy.clear();
+    }
+
+    /// tell `policy` the slots lost their node
+    fn vacated(&mut self, slots: &[usize]) {
+        let now = self.clock.now();
+        self.with_policy(now, |policy, view| policy.on_remove(slots, view));
+    }
+
+    /// tell subscribers and the `EvictionListener` a node is gone
+    fn notify(&mut self, node: Node<K, V>, cause: RemovalCause) {
+        event::publish(&mut self.subscribers, || match cause {
+            RemovalCause::Explicit => Event::Remove(node.key.clone(), node.value.clone()),
+            cause => Event::Evict(node.key.clone(), node.value.clone(), cause),
+        });
+        self.listen(node, cause);
+    }
+}
+
+/// `Probe` over `nodes` as of `now`
+struct NodeProbe<'a, K, V> {
+    nodes: &'a mut [Option<Node<K, V>>],
+    now: Duration,
+    idle: Option<Duration>,
+}
+
+impl<K: Hash, V> Probe for NodeProbe<'_, K, V> {
+    fn count(&self, slot: usize) -> u64 {
+        self.nodes
+            .get(slot)
+            .and_then(Option::as_ref)
+            .map_or(0, |node| node.count)
+    }
+
+    fn set_count(&mut self, slot: usize, count: u64) {
+        if let Some(Some(node)) = self.nodes.get_mut(slot) {
+            node.count = count;
+        }
+    }
+
+    fn occupied(&self, slot: usize) -> bool {
+        self.nodes.get(slot).is_some_and(Option::is_some)
+    }
+
+    fn outdated(&self, slot: usize) -> bool {
+        self.nodes
+            .get(slot)
+            .and_then(Option::as_ref)
+            .is_none_or(|node| node.outdated(self.now, self.idle))
+    }
+
+    fn hash(&self, slot: usize) -> Option<u64> {
+        let node = self.nodes.get(slot)?.as_ref()?;
+        Some(fingerprint(&node.key))
+    }
+}
+
+#[repr(align(128))]
+#[derive(Clone)]
+struct Node<K, V> {
+    key: K,
+    value: V,
+    epoch: Duration,
+    /// last write or daemon applied read, against `config.idle`
+    access: Duration,
+    /// `config.duration` unless put through `put_with_ttl` or decided by `Expiry`
+    ttl: Duration,
+    count: u64,
+}
+
+impl<K, V> Node<K, V> {
+    fn remaining(&self, now: Duration) -> Duration {
+        self.epoch.saturating_add(self.ttl).saturating_sub(now)
+    }
+
+    /// old enough for a read to schedule its reload
+    fn stale(&self, now: Duration, refresh: Option<Duration>) -> bool {
+        refresh.is_some_and(|refresh| now.saturating_sub(self.epoch) >= refresh)
+    }
+
+    /// the instant `outdated` turn true unless read or written again
+    fn deadline(&self, idle: Option<Duration>) -> Duration {
+        let deadline = self.epoch.saturating_add(self.ttl);
+        idle.map_or(deadline, |idle| {
+            deadline.min(self.access.saturating_add(idle))
+        })
+    }
+
+    fn outdated(&self, now: Duration, idle: Option<Duration>) -> bool {
+        now.saturating_sub(self.epoch) >= self.ttl
+            || idle.is_some_and(|idle| now.saturating_sub(self.access) >= idle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicBool;
+    use std::sync::atomic::Ordering;
+
+    use super::*;
+
+    fn count<K, V, P>(state: &Cache<K, V, P>, slot: usize) -> u64 {
+        state.nodes[slot].as_ref().map_or(0, |node| node.count)
+    }
+
+    fn config(capacity: usize) -> Config {
+        Config {
+            capacity,
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn ring_overwrites_oldest() {
+        let cache = DualCacheFF::<_, _>::manual(config(3), Hooks::default());
+        for i in 0..4 {
+            cache.put(i, i);
+        }
+
+        assert!(cache.get(&0).is_none());
+        assert_eq!(cache.get(&3), Some(3));
+        let state = cache.main.lock().unwrap();
+        assert_eq!(state.index.get(&3), Some(&0));
+        assert_eq!(state.policy.arena(), vec![1, 2, 0]);
+    }
+
+    #[test]
+    fn put_updates_in_place() {
+        let cache = DualCacheFF::<_, _>::manual(config(3), Hooks::default());
+        cache.put("A", 1);
+        cache.put("A", 2);
+
+        assert_eq!(cache.get("A"), Some(2));
+        assert_eq!(cache.main.lock().unwrap().nodes.len(), 1);
+    }
+
+    #[test]
+    fn apply_climbs_and_protects() {
+        let cache = DualCacheFF::<_, _>::manual(config(4), Hooks::default());
+        for i in 0..4 {
+            cache.put(i, i);
+        }
+        {
+            let mut state = cache.main.lock().unwrap();
+            let now = state.clock.now();
+            state.apply([2, 2, 2], now);
+            state.calibrate();
+            assert_eq!(state.policy.arena(), vec![2, 0, 1, 3]);
+            assert_eq!(state.policy.rank(), vec![1, 2, 0, 3]);
+            assert_eq!(state.policy.evict_point, 1);
+        }
+        cache.put(4, 4);
+        cache.put(5, 5);
+        cache.put(6, 6);
+
+        assert_eq!(cache.get(&2), Some(2));
+        assert!(cache.get(&3).is_none());
+    }
+
+    #[test]
+    fn count_freezes() {
+        let cache = DualCacheFF::<_, _>::manual(config(100), Hooks::default());
+        cache.put(0, 0);
+        let mut state = cache.main.lock().unwrap();
+        let now = state.clock.now();
+        state.apply(std::iter::repeat_n(0, 100), now);
+
+        assert_eq!(state.nodes[0].as_ref().unwrap().count, 10);
+        assert_eq!(state.lookup_count, 100);
+    }
+
+    #[test]
+    fn remove_vacates_slot() {
+        let cache = DualCacheFF::<_, _>::manual(config(3), Hooks::default());
+        for i in 0..3 {
+            cache.put(i, i);
+        }
+        {
+            let mut state = cache.main.lock().unwrap();
+            let now = state.clock.now();
+            state.apply([1, 1], now);
+            state.calibrate();
+            assert_eq!(state.policy.arena(), vec![1, 0, 2]);
+            assert_eq!(state.policy.evict_point, 1);
+        }
+
+        assert_eq!(cache.remove(&1), Some(1));
+        assert_eq!(cache.remove(&1), None);
+        {
+            let state = cache.main.lock().unwrap();
+            assert_eq!(state.policy.arena(), vec![0, 2, 1]);
+            assert_eq!(state.policy.rank(), vec![0, 2, 1]);
+            assert_eq!(state.vacant, vec![1]);
+            assert_eq!(state.policy.evict_point, 0);
+        }
+
+        cache.put(3, 3);
+        assert_eq!(cache.get(&0), Some(0));
+        assert_eq!(cache.get(&3), Some(3));
+        assert_eq!(cache.main.lock().unwrap().index.get(&3), Some(&1));
+    }
+
+    #[test]
+    fn invalidate_if_sinks_vacated() {
+        let cache = DualCacheFF::<_, _>::manual(config(4), Hooks::default());
+        for i in 0..4 {
+            cache.put(i, i * 10);
+        }
+        {
+            let mut state = cache.main.lock().unwrap();
+            let now = state.clock.now();
+            state.apply([2, 2, 2], now);
+            state.calibrate();
+            assert_eq!(state.policy.arena(), vec![2, 0, 1, 3]);
+            assert_eq!(state.policy.evict_point, 1);
+        }
+
+        assert_eq!(
+            cache.invalidate_if(|&key, &value| key == 2 || value == 30),
+            2
+        );
+        assert_eq!(cache.invalidate_if(|_, _| false), 0);
+        let state = cache.main.lock().unwrap();
+        assert_eq!(state.policy.arena(), vec![0, 1, 2, 3]);
+        assert_eq!(state.policy.rank(), vec![0, 1, 2, 3]);
+        assert_eq!(state.vacant, vec![2, 3]);
+        assert_eq!(state.policy.evict_point, 0);
+        assert_eq!(state.index.len(), 2);
+    }
+
+    #[test]
+    fn invalidate_if_survives_panic() {
+        let cache = DualCacheFF::<_, _>::manual(config(4), Hooks::default());
+        for i in 0..3 {
+            cache.put(i, i);
+        }
+        let panicked = std::panic::catch_unwind(AssertUnwindSafe(|| {
+            cache.invalidate_if(|&key, _| key == 0 || panic!("predicate"))
+        }));
+
+        assert!(panicked.is_err());
+        assert!(!cache.main.is_poisoned());
+        assert!((0..3).all(|i| cache.get(&i) == Some(i)));
+        cache.put(3, 3);
+        assert_eq!(cache.invalidate_if(|_, _| true), 4);
+    }
+
+    #[test]
+    fn entry_survives_panic() {
+        let cache = DualCacheFF::<_, _>::manual(config(3), Hooks::default());
+        cache.put("A", 1);
+        let produced = std::panic::catch_unwind(AssertUnwindSafe(|| {
+            cache.entry("B").or_insert_with(|| panic!("producer"))
+        }));
+        let modified = std::panic::catch_unwind(AssertUnwindSafe(|| {
+            cache.entry("A").and_modify(|value| {
+                *value = 2;
+                panic!("modify")
+            });
+        }));
+
+        assert!(produced.is_err() && modified.is_err());
+        assert!(!cache.main.is_poisoned());
+        assert_eq!(cache.get("A"), Some(1));
+        assert!(cache.get("B").is_none());
+        cache.put("B", 3);
+        assert_eq!(cache.invalidate_if(|_, _| true), 2);
+    }
+
+    #[test]
+    fn entry_skips_outdated() {
+        let clock = Arc::new(MockClock::new());
+        let hooks = Hooks {
+            clock: Some(clock.clone()),
+            ..Hooks::default()
+        };
+        let cache = DualCacheFF::<_, _>::manual(config(3), hooks);
+        cache.put("A", 1);
+        clock.advance(Duration::from_secs(5));
+
+        let value = cache
+            .entry("A")
+            .and_modify(|value| *value += 1)
+            .or_insert(5);
+        assert_eq!(value, 5);
+        assert_eq!(cache.get("A"), Some(5));
+
+        let mut calls = 0;
+        for _ in 0..2 {
+            cache.entry("B").or_insert_with(|| {
+                calls += 1;
+                7
+            });
+        }
+        assert_eq!(calls, 1);
+        assert_eq!(cache.lazy_rx.len(), 2);
+    }
+
+    #[test]
+    fn flight_shares_error() {
+        let cache = DualCacheFF::<_, _>::manual(config(3), Hooks::default());
+        let (entered_tx, entered_rx) = bounded(0);
+        let (release_tx, release_rx) = bounded::<()>(0);
+
+        std::thread::scope(|scope| {
+            let leader = scope.spawn(|| {
+                cache.try_get_with("A", || {
+                    entered_tx.send(()).unwrap();
+                    release_rx.recv().unwrap();
+                    Err(anyhow::anyhow!("down"))
+                })
+            });
+            entered_rx.recv().unwrap();
+            let waiter = scope.spawn(|| cache.try_get_with("A", || Ok(1)));
+            while Arc::strong_count(&cache.flights.lock().unwrap()["A"]) < 3 {
+                std::thread::yield_now();
+            }
+            release_tx.send(()).unwrap();
+
+            let leader = leader.join().unwrap().err().unwrap();
+            let waiter = waiter.join().unwrap().err().unwrap();
+            assert!(Arc::ptr_eq(&leader, &waiter));
+        });
+
+        assert!(cache.flights.lock().unwrap().is_empty());
+        assert!(cache.get("A").is_none());
+    }
+
+    #[test]
+    fn flight_survives_panic() {
+        let cache = DualCacheFF::<_, _>::manual(config(3), Hooks::default());
+        let panicked = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            cache.get_with("A", || panic!("loader"))
+        }));
+
+        assert!(panicked.is_err());
+        assert!(cache.flights.lock().unwrap().is_empty());
+        assert_eq!(cache.get_with("A", || 1), 1);
+    }
+
+    #[test]
+    fn loader_required() {
+        let cache = DualCacheFF::<u32, u32>::manual(config(3), Hooks::default());
+        let err = cache.get_or_load(&1).err().unwrap();
+        assert_eq!(err.to_string(), "no CacheLoader configured");
+
+        cache.put(1, 1);
+        assert_eq!(cache.get_or_load(&1).unwrap(), 1);
+        assert!(cache.get_all_or_load([1, 2]).is_err());
+    }
+
+    #[test]
+    fn read_through_loads_miss() {
+        let store = Arc::new(MemoryStore::<String, u32>::new());
+        store.write(&"A".to_string(), &1).unwrap();
+        let hooks = Hooks {
+            loader: Some(store.clone() as _),
+            ..Hooks::default()
+        };
+        let cache = DualCacheFF::<String, u32>::manual(config(3), hooks);
+
+        assert!(cache.get("A").is_none());
+        assert_eq!(cache.read_through("A"), Some(1));
+        assert!(cache.read_through("B").is_none());
+        store.write(&"A".to_string(), &2).unwrap();
+        assert_eq!(cache.get("A"), Some(1));
+
+        let store = Arc::new(MemoryStore::<&'static str, u32>::new());
+        store.write(&"A", &1).unwrap();
+        let hooks = Hooks {
+            loader: Some(store as _),
+            ..Hooks::default()
+        };
+        let cache = DualCacheFF::<&'static str, u32>::manual(config(3), hooks);
+        assert_eq!(cache.read_through(&"A"), Some(1));
+        assert_eq!(cache.get("A"), Some(1));
+    }
+
+    #[test]
+    fn refresh_ahead_reloads_in_background() {
+        let clock = Arc::new(MockClock::new());
+        let store = Arc::new(MemoryStore::new());
+        let hooks = Hooks {
+            loader: Some(store.clone()),
+            clock: Some(clock.clone()),
+            ..Hooks::default()
+        };
+        let cache = DualCacheFF::<_, _>::manual(
+            Config {
+                refresh: Some(Duration::from_secs(3)),
+                ..config(3)
+            },
+            hooks,
+        );
+        cache.put("A", 1);
+        store.write(&"A", &2).unwrap();
+
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(cache.get("A"), Some(1));
+        assert!(cache.refresh_rx.is_empty());
+
+        clock.advance(Duration::from_secs(3));
+        assert_eq!(cache.get("A"), Some(1));
+        assert_eq!(cache.get("A"), Some(1));
+        cache.run_pending_tasks();
+        assert_eq!(cache.get("A"), Some(2));
+
+        clock.advance(Duration::from_secs(3));
+        cache.get("A");
+        cache.put("A", 3);
+        cache.run_pending_tasks();
+        assert_eq!(cache.get("A"), Some(3));
+    }
+
+    /// panic on every load and on writes of "boom", store the other writes
+    struct Explode(MemoryStore<&'static str, u32>);
+
+    impl CacheLoader<&'static str, u32> for Explode {
+        fn load(&self, _: &&'static str) -> anyhow::Result<u32> {
+            panic!("loader")
+        }
+    }
+
+    impl CacheWriter<&'static str, u32> for Explode {
+        fn write(&self, key: &&'static str, value: &u32) -> anyhow::Result<()> {
+            assert_ne!(*key, "boom");
+            self.0.write(key, value)
+        }
+
+        fn delete(&self, key: &&'static str) -> anyhow::Result<()> {
+            self.0.delete(key)
+        }
+    }
+
+    #[test]
+    fn daemon_survives_panicking_hooks() {
+        let clock = Arc::new(MockClock::new());
+        let explode = Arc::new(Explode(MemoryStore::new()));
+        let hooks = Hooks {
+            loader: Some(explode.clone()),
+            writer: Some((explode.clone() as _, WriteMode::Behind)),
+            clock: Some(clock.clone()),
+            ..Hooks::default()
+        };
+        let (cache, daemon) = DualCacheFF::<_, _>::spawn(
+            Config {
+                refresh: Some(Duration::from_secs(3)),
+                ..config(3)
+            },
+            hooks,
+            "test".to_string(),
+        )
+        .unwrap();
+        cache.put("A", 1);
+        clock.advance(Duration::from_secs(4));
+        assert_eq!(cache.get("A"), Some(1));
+        cache.put("B", 2);
+        cache.put("boom", 3);
+        daemon.shutdown().unwrap();
+
+        assert_eq!(cache.get("A"), Some(1));
+        assert_eq!(explode.0.get(&"A"), Some(1));
+        assert_eq!(explode.0.get(&"B"), Some(2));
+        assert_eq!(explode.0.get(&"boom"), None);
+    }
+
+    #[test]
+    fn stale_kept_through_grace() {
+        let clock = Arc::new(MockClock::new());
+        let store = Arc::new(MemoryStore::new());
+        let hooks = Hooks {
+            loader: Some(store.clone()),
+            clock: Some(clock.clone()),
+            ..Hooks::default()
+        };
+        let cache = DualCacheFF::<_, _>::manual(
+            Config {
+                grace: Some(Duration::from_secs(60)),
+                ..config(3)
+            },
+            hooks,
+        );
+        cache.put("A", 1);
+
+        clock.advance(Duration::from_secs(30));
+        cache.run_pending_tasks();
+        assert!(cache.get("A").is_none());
+        assert_eq!(cache.get_or_load_stale(&"A").unwrap(), Served::Stale(1));
+
+        store.write(&"A", &2).unwrap();
+        assert_eq!(cache.get_or_load_stale(&"A").unwrap(), Served::Fresh(2));
+
+        clock.advance(Duration::from_secs(70));
+        cache.run_pending_tasks();
+        assert!(cache.main.lock().unwrap().index.is_empty());
+    }
+
+    struct Refuse;
+
+    impl CacheWriter<&'static str, u32> for Refuse {
+        fn write(&self, key: &&'static str, value: &u32) -> anyhow::Result<()> {
+            anyhow::ensure!(*key != "bad" && *value != 0, "refused");
+            Ok(())
+        }
+
+        fn delete(&self, _: &&'static str) -> anyhow::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn write_through_failure_not_cached() {
+        let hooks = Hooks {
+            writer: Some((Arc::new(Refuse) as _, WriteMode::Through)),
+            ..Hooks::default()
+        };
+        let cache = DualCacheFF::<_, _>::manual(config(3), hooks);
+
+        assert_eq!(
+            cache.try_put("bad", 1u32).err().unwrap().to_string(),
+            "refused"
+        );
+        cache.put("bad", 1);
+        assert!(cache.get("bad").is_none());
+        assert!(cache.try_put("good", 1).is_ok());
+        assert_eq!(cache.get("good"), Some(1));
+    }
+
+    #[test]
+    fn entry_write_through_failure_not_cached() {
+        let hooks = Hooks {
+            writer: Some((Arc::new(Refuse) as _, WriteMode::Through)),
+            ..Hooks::default()
+        };
+        let cache = DualCacheFF::<_, _>::manual(config(3), hooks);
+
+        assert_eq!(cache.entry("bad").or_insert(1u32), 1);
+        assert!(cache.get("bad").is_none());
+        let loaded: anyhow::Result<u32> = cache.entry("bad").or_try_insert_with(|| Ok(1));
+        assert_eq!(loaded.err().unwrap().to_string(), "refused");
+        assert!(cache.get("bad").is_none());
+
+        cache.put("good", 1);
+        cache.entry("good").and_modify(|value| *value = 0);
+        assert_eq!(cache.get("good"), Some(1));
+        cache.entry("good").and_modify(|value| *value = 2);
+        assert_eq!(cache.get("good"), Some(2));
+    }
+
+    #[test]
+    fn write_through_follows_cache_order() {
+        let store = Arc::new(MemoryStore::new());
+        let hooks = Hooks {
+            writer: Some((store.clone() as _, WriteMode::Through)),
+            ..Hooks::default()
+        };
+        let cache = DualCacheFF::<_, _>::manual(config(4), hooks);
+        std::thread::scope(|scope| {
+            for thread in 0..4u32 {
+                let cache = &cache;
+                scope.spawn(move || {
+                    for i in 0..500 {
+                        match i % 3 {
+                            0 => drop(cache.remove("A")),
+                            _ => cache.put("A", thread * 1_000 + i),
+                        }
+                    }
+                });
+            }
+        });
+
+        assert_eq!(store.get(&"A"), cache.get("A"));
+    }
+
+    #[test]
+    fn write_through_outside_main() {
+        /// hold the write of key 0 until `open` fire
+        struct Gate {
+            entered: Sender<()>,
+            open: Receiver<()>,
+        }
+
+        impl CacheWriter<u32, u32> for Gate {
+            fn write(&self, key: &u32, _: &u32) -> anyhow::Result<()> {
+                if *key == 0 {
+                    self.entered.send(()).unwrap();
+                    self.open.recv().unwrap();
+                }
+                Ok(())
+            }
+
+            fn delete(&self, _: &u32) -> anyhow::Result<()> {
+                Ok(())
+            }
+        }
+
+        let (entered, parked) = bounded(1);
+        let (open, gate) = bounded(1);
+        let hooks = Hooks {
+            writer: Some((
+                Arc::new(Gate {
+                    entered,
+                    open: gate,
+                }) as _,
+                WriteMode::Through,
+            )),
+            ..Hooks::default()
+        };
+        let cache = DualCacheFF::<u32, u32>::manual(config(4), hooks);
+        std::thread::scope(|scope| {
+            scope.spawn(|| cache.put(0, 0));
+            parked.recv().unwrap();
+
+            cache.put(1, 1);
+            assert_eq!(cache.entry(2).or_insert(2), 2);
+            assert_eq!(cache.remove(&1), Some(1));
+            assert!(cache.get(&0).is_none());
+            open.send(()).unwrap();
+        });
+
+        assert_eq!(cache.get(&0), Some(0));
+    }
+
+    #[test]
+    fn write_behind_flushed_on_shutdown() {
+        let store = Arc::new(MemoryStore::new());
+        let hooks = Hooks {
+            writer: Some((store.clone() as _, WriteMode::Behind)),
+            ..Hooks::default()
+        };
+        let (cache, daemon) =
+            DualCacheFF::<_, _>::spawn(config(4), hooks, "test".to_string()).unwrap();
+        cache.put(1, 1);
+        cache.put(2, 2);
+        cache.remove(&1);
+        cache.invalidate(&2);
+        daemon.shutdown().unwrap();
+
+        assert_eq!(store.get(&1), None);
+        assert_eq!(store.get(&2), Some(2));
+        assert!(cache.behind_rx.is_empty());
+    }
+
+    #[test]
+    fn write_behind_flushed_on_drop() {
+        let store = Arc::new(MemoryStore::<u32, u32>::new());
+        let removed = Arc::new(Mutex::new(0));
+        let count = removed.clone();
+        let hooks = Hooks {
+            writer: Some((store.clone() as _, WriteMode::Behind)),
+            listener: Some(Arc::new(move |_, _, _| *count.lock().unwrap() += 1)),
+            ..Hooks::default()
+        };
+        let (cache, daemon) =
+            DualCacheFF::<u32, u32>::spawn(config(4), hooks, "test".to_string()).unwrap();
+        for i in 0..100 {
+            cache.put(i, i);
+        }
+        cache.remove(&99);
+        drop(cache);
+        daemon.join().unwrap();
+
+        assert!((0..99).all(|i| store.get(&i) == Some(i)));
+        assert_eq!(store.get(&99), None);
+        assert_eq!(*removed.lock().unwrap(), 97);
+    }
+
+    /// fail the first write it is handed, then store everything
+    struct Flaky {
+        store: MemoryStore<&'static str, u32>,
+        fail: AtomicBool,
+    }
+
+    impl CacheWriter<&'static str, u32> for Flaky {
+        fn write(&self, key: &&'static str, value: &u32) -> anyhow::Result<()> {
+            anyhow::ensure!(!self.fail.swap(false, Ordering::SeqCst), "unavailable");
+            self.store.write(key, value)
+        }
+
+        fn delete(&self, key: &&'static str) -> anyhow::Result<()> {
+            self.store.delete(key)
+        }
+    }
+
+    #[test]
+    fn write_behind_failed_batch_retried() {
+        let flaky = Arc::new(Flaky {
+            store: MemoryStore::new(),
+            fail: AtomicBool::new(true),
+        });
+        let hooks = Hooks {
+            writer: Some((flaky.clone() as _, WriteMode::Behind)),
+            ..Hooks::default()
+        };
+        let cache = DualCacheFF::<_, _>::manual(config(4), hooks);
+        cache.put("A", 1u32);
+        cache.run_pending_tasks();
+        assert_eq!(flaky.store.get(&"A"), None);
+
+        cache.put("A", 2);
+        cache.put("B", 3);
+        cache.run_pending_tasks();
+        assert_eq!(flaky.store.get(&"A"), Some(2));
+        assert_eq!(flaky.store.get(&"B"), Some(3));
+        assert_eq!(flaky.store.writes(), 2);
+    }
+
+    #[test]
+    fn outdated_evicted_first() {
+        let cache = DualCacheFF::<_, _>::manual(config(3), Hooks::default());
+        cache.put(0, 0);
+        cache.put_with_ttl(1, 1, Duration::ZERO);
+        cache.put(2, 2);
+        cache.main.lock().unwrap().policy.protect(3);
+        cache.put(3, 3);
+
+        assert_eq!(cache.get(&0), Some(0));
+        assert_eq!(cache.get(&2), Some(2));
+        assert_eq!(cache.main.lock().unwrap().index.get(&3), Some(&1));
+
+        cache.put(2, 20);
+        assert_eq!(cache.mirror.node(&2).unwrap().ttl, Duration::from_secs(5));
+    }
+
+    #[test]
+    fn idle_refreshed_by_promotion() {
+        let cache = DualCacheFF::<_, _>::manual(
+            Config {
+                duration: Duration::from_secs(3_600),
+                idle: Some(Duration::from_secs(60)),
+                ..config(3)
+            },
+            Hooks::default(),
+        );
+        cache.put("A", 1);
+        cache.put("B", 2);
+        let start = cache.main.lock().unwrap().clock.now();
+        cache.get("A");
+        cache
+            .main
+            .lock()
+            .unwrap()
+            .apply(cache.lazy_rx.try_iter(), start + Duration::from_secs(40));
+
+        let state = cache.main.lock().unwrap();
+        let later = start + Duration::from_secs(70);
+        assert!(state.lookup("A", later).is_some());
+        assert!(state.lookup("B", later).is_none());
+        assert!(state.lookup("A", later + Duration::from_secs(40)).is_none());
+    }
+
+    #[test]
+    fn listener_told_cause() {
+        let clock = Arc::new(MockClock::new());
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let log = seen.clone();
+        let hooks = Hooks {
+            clock: Some(clock.clone()),
+            listener: Some(Arc::new(move |key, _, cause| {
+                log.lock().unwrap().push((key, cause))
+            })),
+            ..Hooks::default()
+        };
+        let cache = DualCacheFF::<u32, u32>::manual(config(2), hooks);
+        cache.put(0, 0);
+        cache.put(1, 1);
+        cache.main.lock().unwrap().policy.protect(2);
+        cache.put(2, 2);
+        cache.run_pending_tasks();
+        assert_eq!(*seen.lock().unwrap(), [(0, RemovalCause::Overwritten)]);
+
+        clock.advance(Duration::from_secs(5));
+        cache.run_pending_tasks();
+        cache.put(3, 3);
+        cache.invalidate_all();
+        cache.run_pending_tasks();
+        let mut seen = seen.lock().unwrap().split_off(1);
+        seen.sort();
+        assert_eq!(
+            seen,
+            [
+                (1, RemovalCause::Expired),
+                (2, RemovalCause::Expired),
+                (3, RemovalCause::Explicit)
+            ]
+        );
+    }
+
+    #[test]
+    fn subscriber_lags_and_unsubscribes() {
+        let cache = DualCacheFF::<_, _>::manual(
+            Config {
+                bound: 2,
+                ..config(1)
+            },
+            Hooks::default(),
+        );
+        let lagging = cache.subscribe();
+        let dropped = cache.subscribe();
+        drop(dropped);
+        cache.put(0, 0);
+        cache.put(1, 1);
+        cache.put(2, 2);
+
+        assert_eq!(
+            lagging.try_iter().collect::<Vec<_>>(),
+            [
+                Event::Insert(0, 0),
+                Event::Evict(0, 0, RemovalCause::Evicted)
+            ]
+        );
+        assert_eq!(cache.main.lock().unwrap().subscribers.len(), 1);
+    }
+
+    #[test]
+    fn wheel_vacates_due_nodes() {
+        let clock = Arc::new(MockClock::new());
+        let hooks = Hooks {
+            clock: Some(clock.clone()),
+            ..Hooks::default()
+        };
+        let cache = DualCacheFF::<_, _>::manual(config(3), hooks);
+        cache.put("A", 1);
+        cache.put_with_ttl("B", 2, Duration::from_secs(60));
+        cache.put_with_ttl("C", 3, Duration::from_secs(7_200));
+
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(cache.main.lock().unwrap().expire(clock.now()), 0);
+        clock.advance(Duration::from_secs(89));
+        assert_eq!(cache.main.lock().unwrap().expire(clock.now()), 2);
+        {
+            let state = cache.main.lock().unwrap();
+            assert_eq!(state.index.len(), 1);
+            assert_eq!(state.policy.arena()[0], state.index["C"]);
+            assert_eq!(state.vacant.len(), 2);
+        }
+
+        clock.advance(Duration::from_secs(7_200));
+        cache.run_pending_tasks();
+        assert!(cache.main.lock().unwrap().index.is_empty());
+        assert!(cache.mirror.node("C").is_none());
+    }
+
+    struct Sliding;
+
+    impl Expiry<u32, u32> for Sliding {
+        fn expire_after_create(&self, _: &u32, value: &u32) -> Duration {
+            Duration::from_secs(*value as u64)
+        }
+
+        fn expire_after_read(&self, _: &u32, _: &u32, remaining: Duration) -> Duration {
+            remaining + Duration::from_secs(10)
+        }
+    }
+
+    #[test]
+    fn expiry_callbacks() {
+        let hooks = Hooks {
+            expiry: Some(Arc::new(Sliding) as _),
+            ..Hooks::default()
+        };
+        let cache = DualCacheFF::<_, _>::manual(config(3), hooks);
+        cache.put(1, 30);
+        let start = cache.main.lock().unwrap().nodes[0].as_ref().unwrap().epoch;
+        let ttl = |cache: &DualCacheFF<u32, u32>| cache.mirror.node(&1).unwrap().ttl;
+        assert_eq!(ttl(&cache), Duration::from_secs(30));
+
+        cache
+            .main
+            .lock()
+            .unwrap()
+            .apply([1], start + Duration::from_secs(5));
+        cache.run_pending_tasks();
+        assert_eq!(ttl(&cache), Duration::from_secs(40));
+
+        cache.entry(1).and_modify(|value| *value = 20);
+        assert_eq!(ttl(&cache), Duration::from_secs(20));
+        cache.put_with_ttl(1, 20, Duration::from_secs(1));
+        assert_eq!(ttl(&cache), Duration::from_secs(1));
+    }
+
+    struct Faulty;
+
+    impl Expiry<u32, u32> for Faulty {
+        fn expire_after_create(&self, _: &u32, _: &u32) -> Duration {
+            panic!("create")
+        }
+
+        fn expire_after_read(&self, _: &u32, _: &u32, _: Duration) -> Duration {
+            panic!("read")
+        }
+    }
+
+    #[test]
+    fn expiry_panic_keeps_main() {
+        let hooks = Hooks {
+            expiry: Some(Arc::new(Faulty) as _),
+            ..Hooks::default()
+        };
+        let cache = DualCacheFF::<u32, u32>::manual(config(3), hooks);
+        cache.put(1, 1);
+        assert_eq!(cache.mirror.node(&1).unwrap().ttl, Duration::from_secs(5));
+        cache.get(&1);
+        cache.run_pending_tasks();
+        cache.put(1, 2);
+
+        assert!(!cache.main.is_poisoned());
+        assert_eq!(cache.mirror.node(&1).unwrap().ttl, Duration::from_secs(5));
+        assert_eq!(cache.get(&1), Some(2));
+    }
+
+    #[test]
+    fn clear_forgets_traffic() {
+        let cache = DualCacheFF::<_, _>::manual(config(3), Hooks::default());
+        cache.put(0, 0);
+        let mut state = cache.main.lock().unwrap();
+        let now = state.clock.now();
+        state.apply([0, 0], now);
+        drop(state);
+
+        cache.invalidate_all();
+        assert_eq!(cache.main.lock().unwrap().lookup_count, 2);
+        assert!(cache.get(&0).is_none());
+
+        cache.put(0, 0);
+        cache.clear();
+        let state = cache.main.lock().unwrap();
+        assert_eq!(state.lookup_count, 0);
+        assert!(state.nodes.is_empty() && state.policy.arena().is_empty());
+    }
+
+    #[test]
+    fn manual_runs_on_demand() {
+        let cache = DualCacheFF::<_, _>::manual(config(4), Hooks::default());
+        cache.put("A", 1);
+        cache.get("A");
+        cache.get("A");
+        assert_eq!(cache.mirror.node("A").unwrap().count, 0);
+
+        cache.run_pending_tasks();
+        assert_eq!(cache.mirror.node("A").unwrap().count, 2);
+        assert!(cache.lazy_rx.is_empty());
+    }
+
+    #[test]
+    fn shutdown_flushes_promotions() {
+        let (cache, daemon) =
+            DualCacheFF::<_, _>::spawn(config(4), Hooks::default(), "test".to_string()).unwrap();
+        cache.put("A", 1);
+        for _ in 0..3 {
+            cache.get("A");
+        }
+        daemon.shutdown().unwrap();
+
+        assert_eq!(count(&cache.main.lock().unwrap(), 0), 3);
+        assert_eq!(cache.mirror.node("A").unwrap().count, 3);
+    }
+
+    /// overwrite the latest insert, the opposite of FIFO
+    struct Newest(usize);
+
+    impl EvictionPolicy for Newest {
+        fn with_capacity(_: usize) -> Self {
+            Newest(0)
+        }
+
+        fn on_insert(&mut self, slot: usize, _: &mut Slots<'_>) {
+            self.0 = slot;
+        }
+
+        fn on_access(&mut self, _: usize, _: &mut Slots<'_>) {}
+
+        fn on_remove(&mut self, _: &[usize], _: &mut Slots<'_>) {}
+
+        fn victim(&mut self, _: u64, slots: &mut Slots<'_>) -> (usize, RemovalCause) {
+            assert!(slots.occupied(self.0));
+            (self.0, RemovalCause::Evicted)
+        }
+
+        fn clear(&mut self) {}
+    }
+
+    #[test]
+    fn policy_is_pluggable() {
+        let cache = DualCacheFF::builder()
+            .capacity(3)
+            .policy::<Newest>()
+            .build_manual()
+            .unwrap();
+        for i in 0..5 {
+            cache.put(i, i);
+        }
+
+        assert_eq!(cache.get(&0), Some(0));
+        assert_eq!(cache.get(&1), Some(1));
+        assert!(cache.get(&2).is_none() && cache.get(&3).is_none());
+        assert_eq!(cache.get(&4), Some(4));
+    }
+
+    #[test]
+    fn s3fifo_readmits_ghosts() {
+        let cache = DualCacheFF::builder()
+            .capacity(10)
+            .policy::<S3Fifo>()
+            .build_manual()
+            .unwrap();
+        for i in 0..11 {
+            cache.put(i, i);
+        }
+        assert!(cache.get(&0).is_none());
+
+        // 0 is remembered by `ghost` and skip `small`, outliving a scan of one-hit wonders
+        cache.put(0, 0);
+        for i in 11..20 {
+            cache.put(i, i);
+        }
+        assert_eq!(cache.get(&0), Some(0));
+        assert!(cache.get(&1).is_none());
+    }
+
+    #[test]
+    fn ghosts_skip_forgotten() {
+        let mut ghosts = ghost::Ghosts::default();
+        for hash in 0..4 {
+            ghosts.push(hash);
+        }
+        assert!(ghosts.remove(1));
+        assert!(!ghosts.remove(1));
+        ghosts.push(0);
+        ghosts.push(1);
+        assert_eq!(ghosts.len(), 4);
+
+        // 1 was pushed again, only its newest entry count
+        assert_eq!(
+            std::iter::from_fn(|| ghosts.pop()).collect::<Vec<_>>(),
+            [0, 2, 3, 1]
+        );
+    }
+
+    #[test]
+    fn sieve_hand_resumes() {
+        let cache = DualCacheFF::builder()
+            .capacity(4)
+            .policy::<Sieve>()
+            .build_manual()
+            .unwrap();
+        for i in 0..4 {
+            cache.put(i, i);
+        }
+        cache.get(&0);
+        cache.run_pending_tasks();
+        cache.put(4, 4);
+        assert!(cache.get(&1).is_none());
+
+        // 0 lost its visited bit to the hand, yet the hand went on past it
+        cache.put(5, 5);
+        assert!(cache.get(&2).is_none());
+        assert_eq!(cache.get(&0), Some(0));
+    }
+
+    #[test]
+    fn tiny_lfu_halves_sketch() {
+        let cache = DualCacheFF::<_, _>::manual(
+            Config {
+                admission: true,
+                ..config(10)
+            },
+            Hooks::default(),
+        );
+        cache.put(0, 0);
+        let mut state = cache.main.lock().unwrap();
+        let now = state.clock.now();
+        state.apply([0; 8], now);
+        let hot = fingerprint(&0);
+        assert_eq!(state.admission.as_ref().unwrap().frequency(hot), 9);
+
+        // 100 samples in a 10 slot cache, the 9 counted above are halved
+        state.apply((1..92).map(|_| 0), now);
+        let frequency = state.admission.as_ref().unwrap().frequency(hot);
+        assert!(frequency < 9, "{frequency}");
+    }
+
+    #[test]
+    fn tiny_lfu_rejects_into_s3fifo() {
+        let cache = DualCacheFF::builder()
+            .capacity(10)
+            .policy::<S3Fifo>()
+            .tiny_lfu()
+            .build_manual()
+            .unwrap();
+        for i in 0..10 {
+            cache.put(i, i);
+        }
+        cache.put(10, 10);
+        assert!(cache.get(&10).is_none());
+        assert_eq!(cache.get(&0), Some(0));
+
+        // 0 went back to the front of `small` without a ghost, so it is the next to leave
+        cache.put(11, 11);
+        cache.put(11, 11);
+        assert!(cache.get(&0).is_none());
+        assert_eq!(cache.get(&1), Some(1));
+        assert_eq!(cache.get(&11), Some(11));
+    }
+
+    #[test]
+    fn tiny_lfu_rejects_into_adaptive() {
+        let cache = DualCacheFF::builder()
+            .capacity(2)
+            .policy::<Adaptive>()
+            .tiny_lfu()
+            .build_manual()
+            .unwrap();
+        cache.put(0, 0);
+        cache.put(1, 1);
+        cache.put(2, 2);
+        assert!(cache.get(&2).is_none());
+        assert_eq!(cache.get(&0), Some(0));
+        assert_eq!(cache.main.lock().unwrap().policy.target, 0);
+
+        // 0 is still the oldest of `recent`, not promoted to `frequent` by its own ghost
+        cache.put(2, 2);
+        assert!(cache.get(&0).is_none());
+        assert_eq!(cache.get(&1), Some(1));
+        assert_eq!(cache.get(&2), Some(2));
+        assert_eq!(cache.main.lock().unwrap().policy.target, 0);
+    }
+
+    #[test]
+    fn adaptive_target_follows_ghosts() {
+        let cache = DualCacheFF::builder()
+            .capacity(2)
+            .policy::<Adaptive>()
+            .build_manual()
+            .unwrap();
+        cache.put(0, 0);
+        cache.put(1, 1);
+        cache.get(&0);
+        cache.run_pending_tasks();
+        cache.put(2, 2);
+        assert!(cache.get(&1).is_none());
+
+        // 1 come back from `recent_ghosts`, `recent` may now keep one slot
+        cache.put(1, 1);
+        assert_eq!(cache.main.lock().unwrap().policy.target, 1);
+        assert!(cache.get(&0).is_none());
+        assert_eq!(cache.get(&1), Some(1));
+        assert_eq!(cache.get(&2), Some(2));
+    }
+
+    #[test]
+    fn builder_rejects_zero() {
+        let err = DualCacheFF::<u32, u32>::builder()
+            .time_to_live(Duration::ZERO)
+            .build_manual()
+            .err()
+            .unwrap();
+        assert_eq!(err.to_string(), "time to live must be greater than zero");
+
+        let err = DualCacheFF::<u32, u32>::builder()
+            .batch(8, 4)
+            .build()
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "batch range 8..=4 must be non-empty and start above zero"
+        );
+    }
+
+    #[test]
+    fn batch_follows_sma() {
+        let mut batch = Batch::new(&Config {
+            min_batch: 4,
+            max_batch: 16,
+            window: 2,
+            ..Config::default()
+        });
+        let quarter = Duration::from_millis(250);
+        batch.adjust(4, quarter);
+        assert_eq!(batch.size, 8);
+        batch.adjust(8, quarter);
+        assert_eq!(batch.size, 16);
+        batch.adjust(16, quarter);
+        assert_eq!(batch.size, 16);
+
+        // as many keys, but spread over four times as long
+        let second = Duration::from_secs(1);
+        batch.adjust(16, second * 4);
+        batch.adjust(16, second * 4);
+        assert_eq!(batch.size, 8);
+        batch.adjust(1, second);
+        assert_eq!(batch.size, 4);
+        batch.adjust(0, Duration::ZERO);
+        assert_eq!(batch.size, 4);
+    }
+}
+
+//credit signature:
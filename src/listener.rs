@@ -0,0 +1,107 @@
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::panic::AssertUnwindSafe;
+
+use tracing::warn;
+
+use crate::DualCacheFF;
+use crate::EvictionPolicy;
+
+/// why a node left `nodes`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum RemovalCause {
+    /// overwritten by `FifoProbation` once every slot of the ring was protected
+    Overwritten,
+    /// picked as victim by the `EvictionPolicy`
+    Evicted,
+    /// outlived its ttl or tti, swept by the timer wheel or reused by an insert
+    Expired,
+    /// `remove`, `invalidate*` or `clear`
+    Explicit,
+    /// its value was written over by `put`, `Entry` or a refresh
+    Replaced,
+}
+
+#[doc = r#"
+# Feature
+- **Removal cause**
+- **Off the hot path**
+
+# Example
+## Removal cause
+registered by `Builder::eviction_listener`, any `Fn(K, V, RemovalCause)` qualify
+```
+use std::sync::{Arc, Mutex};
+use dual_cache_ff::{DualCacheFF, RemovalCause};
+
+let seen = Arc::new(Mutex::new(Vec::new()));
+let log = seen.clone();
+let cache = DualCacheFF::builder()
+    .capacity(1)
+    .eviction_listener(move |key, value, cause| log.lock().unwrap().push((key, value, cause)))
+    .build_manual()
+    .unwrap();
+cache.put("A", 1);
+cache.put("A", 2);
+cache.put("B", 3);
+cache.remove("B");
+cache.run_pending_tasks();
+
+assert_eq!(
+    *seen.lock().unwrap(),
+    [
+        ("A", 1, RemovalCause::Replaced),
+        ("A", 2, RemovalCause::Evicted),
+        ("B", 3, RemovalCause::Explicit),
+    ]
+);
+```
+
+## Off the hot path
+removals are queued under `main` and handed to the listener by the daemon or
+`run_pending_tasks`, a panicking listener is logged and the daemon keep going
+"#]
+pub trait EvictionListener<K, V>: Send + Sync {
+    fn on_removal(&self, key: K, value: V, cause: RemovalCause);
+}
+
+impl<K, V, F> EvictionListener<K, V> for F
+where
+    F: Fn(K, V, RemovalCause) + Send + Sync,
+{
+    fn on_removal(&self, key: K, value: V, cause: RemovalCause) {
+        self(key, value, cause)
+    }
+}
+
+impl<K, V, P> DualCacheFF<K, V, P>
+where
+    K: Hash + Eq + Clone + Send + Sync + 'static + Debug,
+    V: Clone + Send + Sync + 'static + Debug,
+    P: EvictionPolicy,
+{
+    /// hand queued removals to the configured `EvictionListener`
+    pub(crate) fn notify(&self, removals: impl Iterator<Item = (K, V, RemovalCause)>) -> usize {
+        match &self.hooks.listener {
+            Some(listener) => deliver(listener.as_ref(), removals),
+            None => removals.count(),
+        }
+    }
+}
+
+/// call `listener` once per removal, a panic is logged and the next removal still delivered
+pub(crate) fn deliver<K, V>(
+    listener: &dyn EvictionListener<K, V>,
+    removals: impl Iterator<Item = (K, V, RemovalCause)>,
+) -> usize {
+    let mut processed_count = 0;
+    for (key, value, cause) in removals {
+        processed_count += 1;
+        let notified =
+            std::panic::catch_unwind(AssertUnwindSafe(|| listener.on_removal(key, value, cause)));
+        if notified.is_err() {
+            warn!(?cause, "eviction listener panicked");
+        }
+    }
+    processed_count
+}
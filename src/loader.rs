@@ -0,0 +1,295 @@
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::panic::AssertUnwindSafe;
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use tracing::instrument;
+use tracing::warn;
+
+use crate::DualCacheFF;
+use crate::EvictionPolicy;
+
+#[doc = r#"
+# Feature
+- **Read through**
+- **Batch miss**
+- **Refresh ahead**
+
+# Example
+## Read through
+registered by `Builder::loader`, `DualCacheFF::read_through` and `get_or_load` fall back to
+`load` on miss, `get` stay on the cache alone
+```
+use dual_cache_ff::{CacheLoader, DualCacheFF};
+
+struct Square;
+
+impl CacheLoader<u64, u64> for Square {
+    fn load(&self, key: &u64) -> anyhow::Result<u64> {
+        Ok(key * key)
+    }
+}
+
+let cache = DualCacheFF::builder().loader(Square).build_manual().unwrap();
+
+assert_eq!(cache.get(&12), None);
+assert_eq!(cache.read_through(&12), Some(144));
+assert_eq!(cache.get_or_load(&13).unwrap(), 169);
+```
+
+## Batch miss
+`DualCacheFF::get_all_or_load` hand every missing key to one `load_all`, which default to
+calling `load` per key
+
+## Refresh ahead
+with `Builder::refresh_after`, a `get` on a node older than it still hit and queue the key,
+the daemon or `run_pending_tasks` call `load` and swap the value in unless it was written meanwhile
+"#]
+pub trait CacheLoader<K, V>: Send + Sync {
+    fn load(&self, key: &K) -> anyhow::Result<V>;
+
+    /// load several keys at once, keys left out of the map are treated as absent
+    fn load_all(&self, keys: &[K]) -> anyhow::Result<HashMap<K, V>>
+    where
+        K: Clone + Hash + Eq,
+    {
+        keys.iter()
+            .map(|key| Ok((key.clone(), self.load(key)?)))
+            .collect()
+    }
+}
+
+impl<K, V, L: CacheLoader<K, V> + ?Sized> CacheLoader<K, V> for std::sync::Arc<L> {
+    fn load(&self, key: &K) -> anyhow::Result<V> {
+        (**self).load(key)
+    }
+
+    fn load_all(&self, keys: &[K]) -> anyhow::Result<HashMap<K, V>>
+    where
+        K: Clone + Hash + Eq,
+    {
+        (**self).load_all(keys)
+    }
+}
+
+/// value handed back by `DualCacheFF::get_or_load_stale`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Served<V> {
+    /// cached and within its lifetime, or just loaded
+    Fresh(V),
+    /// outdated within the `Builder::serve_stale` grace, served because its load failed
+    Stale(V),
+}
+
+impl<V> Served<V> {
+    pub fn is_stale(&self) -> bool {
+        matches!(self, Served::Stale(_))
+    }
+
+    pub fn into_inner(self) -> V {
+        match self {
+            Served::Fresh(value) | Served::Stale(value) => value,
+        }
+    }
+}
+
+impl<K, V, P> DualCacheFF<K, V, P>
+where
+    K: Hash + Eq + Clone + Send + Sync + 'static + Debug,
+    V: Clone + Send + Sync + 'static + Debug,
+    P: EvictionPolicy,
+{
+    /// `get`, falling back to the configured `CacheLoader` through the single flight of
+    /// `try_get_with` on miss
+    pub fn get_or_load(&self, key: &K) -> Result<V, Arc<anyhow::Error>> {
+        if let Some(value) = self.hit(key) {
+            return Ok(value);
+        }
+        let loader = self.loader()?;
+        self.try_get_with(key.clone(), || loader.load(key))
+    }
+
+    #[doc = r#"
+    # Feature
+    - **Stale on error**
+
+    # Example
+    ## Stale on error
+    with `Builder::serve_stale`, an outdated node stay in place for the grace period, if
+    `get_or_load` fail on it the previous value come back as `Served::Stale` instead of the error
+    ```
+    use std::time::Duration;
+    use dual_cache_ff::{CacheLoader, DualCacheFF, Served};
+
+    struct Down;
+
+    impl CacheLoader<&'static str, u32> for Down {
+        fn load(&self, _: &&'static str) -> anyhow::Result<u32> {
+            anyhow::bail!("upstream down")
+        }
+    }
+
+    let cache = DualCacheFF::builder()
+        .loader(Down)
+        .time_to_live(Duration::from_millis(1))
+        .serve_stale(Duration::from_secs(60))
+        .build_manual()
+        .unwrap();
+    cache.put("A", 100);
+    std::thread::sleep(Duration::from_millis(5));
+
+    assert!(cache.get("A").is_none());
+    assert_eq!(cache.get_or_load_stale(&"A").unwrap(), Served::Stale(100));
+    assert!(cache.get_or_load_stale(&"B").is_err());
+    ```
+    "#]
+    pub fn get_or_load_stale(&self, key: &K) -> Result<Served<V>, Arc<anyhow::Error>> {
+        match self.get_or_load(key) {
+            Ok(value) => Ok(Served::Fresh(value)),
+            Err(err) => {
+                let node = self
+                    .mirror
+                    .lookup_stale(key, self.mirror.clock.now())
+                    .ok_or_else(|| err.clone())?;
+                warn!(%err, ?key, "load failed, serving stale value");
+                Ok(Served::Stale(node.value.clone()))
+            }
+        }
+    }
+
+    #[doc = r#"
+    # Feature
+    - **One batch per miss set**
+
+    # Example
+    ## One batch per miss set
+    hits come from `mirror`, every miss go to a single `load_all` and each loaded entry is
+    cached without reaching a `CacheWriter`, batch loads are not coalesced with concurrent flights
+    ```
+    use std::collections::HashMap;
+    use dual_cache_ff::{CacheLoader, DualCacheFF};
+
+    struct Double;
+
+    impl CacheLoader<u64, u64> for Double {
+        fn load(&self, key: &u64) -> anyhow::Result<u64> {
+            Ok(key * 2)
+        }
+
+        fn load_all(&self, keys: &[u64]) -> anyhow::Result<HashMap<u64, u64>> {
+            assert_eq!(keys, [2, 3]);
+            Ok(keys.iter().map(|key| (*key, key * 2)).collect())
+        }
+    }
+
+    let cache = DualCacheFF::builder().loader(Double).build_manual().unwrap();
+    cache.put(1, 0);
+    let values = cache.get_all_or_load([1, 2, 3]).unwrap();
+
+    assert_eq!(values, HashMap::from([(1, 0), (2, 4), (3, 6)]));
+    ```
+    "#]
+    pub fn get_all_or_load(
+        &self,
+        keys: impl IntoIterator<Item = K>,
+    ) -> Result<HashMap<K, V>, Arc<anyhow::Error>> {
+        let mut values = HashMap::new();
+        let mut misses = Vec::new();
+        for key in keys {
+            match self.hit(&key) {
+                Some(value) => {
+                    values.insert(key, value);
+                }
+                None => misses.push(key),
+            }
+        }
+        if misses.is_empty() {
+            return Ok(values);
+        }
+
+        let loaded = self.loader()?.load_all(&misses).map_err(Arc::new)?;
+        for (key, value) in loaded {
+            self.fill(key.clone(), value.clone());
+            values.insert(key, value);
+        }
+        Ok(values)
+    }
+
+    /// reload keys queued by `get` past `config.refresh`, a key rewritten or gone since is skipped,
+    /// a failed reload keep the current value
+    pub(crate) fn reload(&self, keys: impl Iterator<Item = K>) -> usize {
+        let mut processed_count = 0;
+        let mut seen = HashSet::new();
+        for key in keys {
+            processed_count += 1;
+            if !seen.insert(key.clone()) {
+                continue;
+            }
+            let Some(loader) = &self.hooks.loader else {
+                continue;
+            };
+            let epoch = {
+                let state = self.main.lock().unwrap();
+                let now = state.clock.now();
+                match state.lookup(&key, now) {
+                    Some(node) if node.stale(now, state.config.refresh) => node.epoch,
+                    _ => continue,
+                }
+            };
+
+            let value = match std::panic::catch_unwind(AssertUnwindSafe(|| loader.load(&key))) {
+                Ok(Ok(value)) => value,
+                Ok(Err(err)) => {
+                    warn!(%err, ?key, "refresh failed");
+                    continue;
+                }
+                Err(_) => {
+                    warn!(?key, "cache loader panicked during refresh");
+                    continue;
+                }
+            };
+            let mut state = self.main.lock().unwrap();
+            let Some(&slot) = state.index.get(&key) else {
+                continue;
+            };
+            if state.nodes[slot]
+                .as_ref()
+                .is_some_and(|node| node.epoch == epoch)
+            {
+                let now = state.clock.now();
+                state.renew(slot, now, None, |slot_value| *slot_value = value);
+                self.mirror.publish(&mut state);
+            }
+        }
+        processed_count
+    }
+
+    /// `get` that load a miss through `get_or_load`, a failed load or a missing `CacheLoader`
+    /// read as a miss, the borrowed key must own back into `K` (`K` itself, `str` of `String`...)
+    #[instrument(skip(self), fields(key = ?key))]
+    pub fn read_through<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized + Debug + ToOwned<Owned = K>,
+    {
+        if let Some(value) = self.hit(key) {
+            return Some(value);
+        }
+        self.hooks.loader.as_ref()?;
+        let key = key.to_owned();
+        self.get_or_load(&key)
+            .inspect_err(|err| warn!(%err, ?key, "read-through failed"))
+            .ok()
+    }
+
+    fn loader(&self) -> Result<&Arc<dyn CacheLoader<K, V>>, Arc<anyhow::Error>> {
+        self.hooks
+            .loader
+            .as_ref()
+            .ok_or_else(|| Arc::new(anyhow!("no CacheLoader configured")))
+    }
+}
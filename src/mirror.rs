@@ -0,0 +1,107 @@
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::Arc;
+use std::time::Duration;
+
+use arc_swap::ArcSwap;
+
+use crate::Cache;
+use crate::Clock;
+use crate::Config;
+use crate::Node;
+use crate::policy::fingerprint;
+
+/// nodes aimed at per shard, a write clone no more than its shard
+const SHARD_SIZE: usize = 32;
+
+type Shard<K, V> = HashMap<K, Arc<Node<K, V>>>;
+
+#[doc = r#"
+# Feature
+- **Lock free read**
+- **Shard publish**
+
+# Example
+## Lock free read
+`DualCacheFF::get` load one shard through `ArcSwap` and never touch `main`
+
+## Shard publish
+`Cache` remember the keys it changed in `dirty`, `publish` clone and store only the shards
+holding them, so a `put` cost one shard instead of the whole cache
+"#]
+pub(crate) struct Mirror<K, V> {
+    shards: Box<[ArcSwap<Shard<K, V>>]>,
+    pub(crate) clock: Arc<dyn Clock>,
+    pub(crate) config: Config,
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> Mirror<K, V> {
+    pub(crate) fn new(clock: Arc<dyn Clock>, config: Config) -> Self {
+        let count = config.capacity.div_ceil(SHARD_SIZE).next_power_of_two();
+        Self {
+            shards: (0..count).map(|_| ArcSwap::default()).collect(),
+            clock,
+            config,
+        }
+    }
+
+    fn shard<Q: Hash + ?Sized>(&self, key: &Q) -> usize {
+        fingerprint(key) as usize & (self.shards.len() - 1)
+    }
+
+    /// the published node of `key`, outdated or not
+    pub(crate) fn node<Q>(&self, key: &Q) -> Option<Arc<Node<K, V>>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.shards[self.shard(key)].load().get(key).cloned()
+    }
+
+    /// the node of `key` unless it outlived its ttl or sat idle past `config.idle`
+    pub(crate) fn lookup<Q>(&self, key: &Q, now: Duration) -> Option<Arc<Node<K, V>>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let node = self.node(key)?;
+        (!node.outdated(now, self.config.idle)).then_some(node)
+    }
+
+    /// the node of `key` even if outdated, as long as it is within `config.grace`
+    pub(crate) fn lookup_stale<Q>(&self, key: &Q, now: Duration) -> Option<Arc<Node<K, V>>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let node = self.node(key)?;
+        let grace = self.config.grace?;
+        (!node.outdated(now.saturating_sub(grace), self.config.idle)).then_some(node)
+    }
+
+    /// store the shards holding the keys `state` changed since the last publish, under `main`
+    pub(crate) fn publish<P>(&self, state: &mut Cache<K, V, P>) {
+        if state.dirty.is_empty() {
+            return;
+        }
+        let mut touched: HashMap<usize, Vec<K>> = HashMap::new();
+        for key in state.dirty.drain() {
+            touched.entry(self.shard(&key)).or_default().push(key);
+        }
+        for (shard, keys) in touched {
+            let mut nodes = Shard::clone(&self.shards[shard].load());
+            for key in keys {
+                let node = state
+                    .index
+                    .get(&key)
+                    .and_then(|&slot| state.nodes[slot].as_ref());
+                match node {
+                    Some(node) => nodes.insert(key, Arc::new(node.clone())),
+                    None => nodes.remove(&key),
+                };
+            }
+            self.shards[shard].store(Arc::new(nodes));
+        }
+    }
+}
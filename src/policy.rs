@@ -0,0 +1,377 @@
+use std::hash::DefaultHasher;
+use std::hash::Hash;
+use std::hash::Hasher;
+
+use crate::RemovalCause;
+
+#[doc = r#"
+# Feature
+- **Slot bookkeeping**
+- **Victim choice**
+- **Side by side**
+
+# Example
+## Slot bookkeeping
+`Cache` own `nodes`, `index` and lifetimes, a policy only see stable slot ids and the
+`Slots` view, it is told of every insert, applied read and vacated slot under `main`
+
+## Victim choice
+asked once every slot is occupied, `candidate` is the key hash about to be written, the
+returned cause reach `EvictionListener` and subscribers
+
+## Side by side
+selected by type, so every policy run the same workload through the same `DualCacheFF`
+```
+use dual_cache_ff::{DualCacheFF, FifoProbation};
+
+let fifo = DualCacheFF::builder()
+    .capacity(2)
+    .policy::<FifoProbation>()
+    .build_manual()
+    .unwrap();
+for i in 0..3 {
+    fifo.put(i, i);
+}
+
+assert!(fifo.get(&0).is_none());
+assert_eq!(fifo.get(&2), Some(2));
+```
+"#]
+pub trait EvictionPolicy: Send + Sync + 'static {
+    fn with_capacity(capacity: usize) -> Self;
+
+    /// `slot` now hold a freshly inserted node
+    fn on_insert(&mut self, slot: usize, slots: &mut Slots<'_>);
+
+    /// a read of `slot` applied by the daemon, after `Slots::lookups` counted it
+    fn on_access(&mut self, slot: usize, slots: &mut Slots<'_>);
+
+    /// `vacated` slots lost their node to a removal or expiration
+    fn on_remove(&mut self, vacated: &[usize], slots: &mut Slots<'_>);
+
+    /// end of a promotion batch
+    fn on_batch(&mut self, slots: &mut Slots<'_>) {
+        let _ = slots;
+    }
+
+    /// slot to overwrite with `candidate` and why its node leave, every slot is occupied
+    fn victim(&mut self, candidate: u64, slots: &mut Slots<'_>) -> (usize, RemovalCause);
+
+    /// the admission filter refused `candidate`, `slot` from the last `victim` keep its node and
+    /// go back where it was, no ghost or adaptation kept for it, seated as a new write unless
+    /// overridden
+    fn on_reject(&mut self, slot: usize, slots: &mut Slots<'_>) {
+        self.on_insert(slot, slots);
+    }
+
+    /// every node was dropped
+    fn clear(&mut self);
+}
+
+/// what `Cache` expose of its nodes to a policy
+pub(crate) trait Probe {
+    fn count(&self, slot: usize) -> u64;
+    fn set_count(&mut self, slot: usize, count: u64);
+    fn occupied(&self, slot: usize) -> bool;
+    fn outdated(&self, slot: usize) -> bool;
+    fn hash(&self, slot: usize) -> Option<u64>;
+}
+
+/// view over `nodes` handed to `EvictionPolicy`, slots are `0..capacity`
+pub struct Slots<'a> {
+    probe: &'a mut dyn Probe,
+    capacity: usize,
+    lookups: u64,
+}
+
+impl<'a> Slots<'a> {
+    pub(crate) fn new(probe: &'a mut dyn Probe, capacity: usize, lookups: u64) -> Self {
+        Self {
+            probe,
+            capacity,
+            lookups,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// reads applied since build, `clear` or the overflow reset
+    pub fn lookups(&self) -> u64 {
+        self.lookups
+    }
+
+    /// `Node::count` of `slot`, zero when vacant
+    pub fn count(&self, slot: usize) -> u64 {
+        self.probe.count(slot)
+    }
+
+    pub fn set_count(&mut self, slot: usize, count: u64) {
+        self.probe.set_count(slot, count);
+    }
+
+    pub fn occupied(&self, slot: usize) -> bool {
+        self.probe.occupied(slot)
+    }
+
+    /// vacant, or past its ttl or tti
+    pub fn outdated(&self, slot: usize) -> bool {
+        self.probe.outdated(slot)
+    }
+
+    /// key hash of the node in `slot`, comparable with `candidate` of `victim`
+    pub fn hash(&self, slot: usize) -> Option<u64> {
+        self.probe.hash(slot)
+    }
+}
+
+/// key hash shared by `Slots::hash` and `EvictionPolicy::victim`
+pub(crate) fn fingerprint<Q: Hash + ?Sized>(key: &Q) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[doc = r#"
+# Feature
+- **First in first out**
+- **Arena probation**
+- **Count probation**
+
+# Example
+## First in first out
+`ring_pointer` loop through the slots and overwrite in insertion order
+
+## Arena probation
+every applied read `climb` its slot one rank up `arena`, ranks below `evict_point` are
+protected, `evict_point` move one step per batch toward the average count
+
+## Count probation
+a node read more than `lookups / capacity` is skipped as well, counts freeze at ten times
+that average, once every node is protected the ring fall back to plain FIFO
+"#]
+pub struct FifoProbation {
+    /// neighbour one rank up `arena`, `NIL` at the top
+    above: Vec<usize>,
+    /// neighbour one rank down `arena`, `NIL` at the tail
+    below: Vec<usize>,
+    top: usize,
+    tail: usize,
+    /// rank below `evict_point`
+    protected: Vec<bool>,
+    /// slot at rank `evict_point`, `NIL` once every seated slot is protected
+    boundary: usize,
+    pub(crate) evict_point: usize,
+    /// slots seated in `arena`, always `0..seated`
+    seated: usize,
+    ring_pointer: usize,
+    capacity: usize,
+}
+
+/// end of `arena` in `FifoProbation::above` and `FifoProbation::below`
+const NIL: usize = usize::MAX;
+
+impl FifoProbation {
+    /// swap `slot` with the slot one rank up, crossing `evict_point` if it stood on it
+    fn climb(&mut self, slot: usize) {
+        let above = self.above[slot];
+        if above == NIL {
+            return;
+        }
+        self.unlink(slot);
+        self.link_above(slot, above);
+        if self.boundary == slot {
+            self.protected[slot] = true;
+            self.protected[above] = false;
+            self.boundary = above;
+        } else if self.boundary == above {
+            self.boundary = slot;
+        }
+    }
+
+    fn next(&mut self) -> usize {
+        let slot = self.ring_pointer;
+        self.ring_pointer = (self.ring_pointer + 1) % self.capacity;
+        slot
+    }
+
+    /// seat a freshly written slot at the tail of `arena`
+    fn enter(&mut self, slot: usize) {
+        if slot == self.seated {
+            self.seated += 1;
+        } else {
+            self.demote(slot);
+            return;
+        }
+        self.link_tail(slot);
+        if self.boundary == NIL {
+            self.boundary = slot;
+        }
+    }
+
+    /// move a seated slot to the tail of `arena`, `evict_point` shrink if it was protected
+    fn demote(&mut self, slot: usize) {
+        if self.protected[slot] {
+            self.protected[slot] = false;
+            self.evict_point -= 1;
+        } else if self.boundary == slot {
+            self.boundary = self.below[slot];
+        }
+        self.unlink(slot);
+        self.link_tail(slot);
+        if self.boundary == NIL {
+            self.boundary = slot;
+        }
+    }
+
+    fn link_tail(&mut self, slot: usize) {
+        self.above[slot] = self.tail;
+        self.below[slot] = NIL;
+        match self.tail {
+            NIL => self.top = slot,
+            tail => self.below[tail] = slot,
+        }
+        self.tail = slot;
+    }
+
+    /// put `slot` one rank up of `below`
+    fn link_above(&mut self, slot: usize, below: usize) {
+        let above = self.above[below];
+        self.above[slot] = above;
+        self.below[slot] = below;
+        self.above[below] = slot;
+        match above {
+            NIL => self.top = slot,
+            above => self.below[above] = slot,
+        }
+    }
+
+    fn unlink(&mut self, slot: usize) {
+        let (above, below) = (self.above[slot], self.below[slot]);
+        match above {
+            NIL => self.top = below,
+            above => self.below[above] = below,
+        }
+        match below {
+            NIL => self.tail = above,
+            below => self.above[below] = above,
+        }
+    }
+
+    /// slots of `arena` from the top down
+    #[cfg(test)]
+    pub(crate) fn arena(&self) -> Vec<usize> {
+        std::iter::successors((self.top != NIL).then_some(self.top), |&slot| {
+            (self.below[slot] != NIL).then_some(self.below[slot])
+        })
+        .collect()
+    }
+
+    /// rank of every seated slot in `arena`
+    #[cfg(test)]
+    pub(crate) fn rank(&self) -> Vec<usize> {
+        let mut rank = vec![0; self.seated];
+        for (nxt, slot) in self.arena().into_iter().enumerate() {
+            rank[slot] = nxt;
+        }
+        rank
+    }
+
+    /// protect the top `evict_point` ranks at once
+    #[cfg(test)]
+    pub(crate) fn protect(&mut self, evict_point: usize) {
+        self.protected.fill(false);
+        self.boundary = NIL;
+        for (nxt, slot) in self.arena().into_iter().enumerate() {
+            if nxt < evict_point {
+                self.protected[slot] = true;
+            } else if self.boundary == NIL {
+                self.boundary = slot;
+            }
+        }
+        self.evict_point = evict_point;
+    }
+}
+
+impl EvictionPolicy for FifoProbation {
+    fn with_capacity(capacity: usize) -> Self {
+        Self {
+            above: vec![NIL; capacity],
+            below: vec![NIL; capacity],
+            top: NIL,
+            tail: NIL,
+            protected: vec![false; capacity],
+            boundary: NIL,
+            evict_point: 0,
+            seated: 0,
+            ring_pointer: 0,
+            capacity,
+        }
+    }
+
+    fn on_insert(&mut self, slot: usize, _: &mut Slots<'_>) {
+        self.enter(slot);
+    }
+
+    fn on_access(&mut self, slot: usize, slots: &mut Slots<'_>) {
+        let freeze = (slots.lookups() / self.capacity as u64)
+            .max(1)
+            .saturating_mul(10);
+        let count = slots.count(slot);
+        if count < freeze {
+            slots.set_count(slot, count + 1);
+        }
+        self.climb(slot);
+    }
+
+    /// move only the vacated slots to the tail of `arena`, the rest keep their rank
+    fn on_remove(&mut self, vacated: &[usize], _: &mut Slots<'_>) {
+        for &slot in vacated {
+            self.demote(slot);
+        }
+    }
+
+    /// step `evict_point` one rank toward the average count
+    fn on_batch(&mut self, slots: &mut Slots<'_>) {
+        let average = slots.lookups() / self.capacity as u64;
+        let prev = match self.boundary {
+            NIL => self.tail,
+            boundary => self.above[boundary],
+        };
+
+        if prev != NIL && slots.count(prev) <= average {
+            self.protected[prev] = false;
+            self.boundary = prev;
+            self.evict_point -= 1;
+        } else if self.boundary != NIL && slots.count(self.boundary) > average {
+            self.protected[self.boundary] = true;
+            self.boundary = self.below[self.boundary];
+            self.evict_point += 1;
+        }
+    }
+
+    /// walk the ring from `ring_pointer` and pick the first slot outdated or on probation,
+    /// falling back to plain FIFO once every node is protected
+    fn victim(&mut self, _: u64, slots: &mut Slots<'_>) -> (usize, RemovalCause) {
+        let average = slots.lookups() / self.capacity as u64;
+
+        for _ in 0..self.capacity {
+            let slot = self.next();
+            if slots.outdated(slot) {
+                return (slot, RemovalCause::Expired);
+            }
+            if !self.protected[slot] && slots.count(slot) <= average {
+                return (slot, RemovalCause::Evicted);
+            }
+        }
+        (self.next(), RemovalCause::Overwritten)
+    }
+
+    /// `victim` only moved `ring_pointer`, the slot keep its rank in `arena`
+    fn on_reject(&mut self, _: usize, _: &mut Slots<'_>) {}
+
+    fn clear(&mut self) {
+        *self = Self::with_capacity(self.capacity);
+    }
+}
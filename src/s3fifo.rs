@@ -0,0 +1,180 @@
+use std::collections::VecDeque;
+
+use crate::EvictionPolicy;
+use crate::RemovalCause;
+use crate::Slots;
+use crate::ghost::Ghosts;
+
+/// frequency bits kept in `Node::count`, reads past it are not counted
+const MAX_FREQUENCY: u64 = 3;
+
+#[doc = r#"
+# Feature
+- **Small probation**
+- **Main reinsertion**
+- **Ghost readmission**
+
+# Example
+## Small probation
+a new key enter `small`, about a tenth of the slots, leaving it unread evict it at once so
+one-hit wonders never reach `main`
+```
+use dual_cache_ff::{DualCacheFF, S3Fifo};
+
+let cache = DualCacheFF::builder()
+    .capacity(10)
+    .policy::<S3Fifo>()
+    .build_manual()
+    .unwrap();
+for i in 0..10 {
+    cache.put(i, i);
+}
+cache.get(&0);
+cache.run_pending_tasks();
+for i in 10..20 {
+    cache.put(i, i);
+}
+
+assert_eq!(cache.get(&0), Some(0));
+assert!(cache.get(&1).is_none());
+```
+
+## Main reinsertion
+a read node leaving `small` move to `main`, where every read earn one more lap, up to three
+
+## Ghost readmission
+hashes of keys evicted from `small` are remembered in `ghost`, a key written again while
+remembered skip `small` and go straight to `main`
+"#]
+pub struct S3Fifo {
+    small: VecDeque<usize>,
+    main: VecDeque<usize>,
+    ghost: Ghosts,
+    small_target: usize,
+    capacity: usize,
+    /// whether the last `victim` left `small` or `main`, `None` if it left neither
+    from_small: Option<bool>,
+    /// slots handed to the running `on_remove`, clear between calls
+    vacating: Vec<bool>,
+}
+
+impl S3Fifo {
+    /// remember `hash` of a key evicted from `small`, as many as `main` can hold
+    fn haunt(&mut self, hash: u64) {
+        self.ghost.push(hash);
+        while self.ghost.len() > self.capacity - self.small_target {
+            self.ghost.pop();
+        }
+    }
+
+    /// forget `hash` if it was remembered, telling whether it was
+    fn exorcise(&mut self, hash: u64) -> bool {
+        self.ghost.remove(hash)
+    }
+}
+
+impl EvictionPolicy for S3Fifo {
+    fn with_capacity(capacity: usize) -> Self {
+        let small_target = (capacity / 10).max(1);
+        Self {
+            small: VecDeque::with_capacity(capacity),
+            main: VecDeque::with_capacity(capacity),
+            ghost: Ghosts::default(),
+            small_target,
+            capacity,
+            from_small: None,
+            vacating: vec![false; capacity],
+        }
+    }
+
+    fn on_insert(&mut self, slot: usize, slots: &mut Slots<'_>) {
+        if let Some(hash) = slots.hash(slot)
+            && self.exorcise(hash)
+        {
+            self.main.push_back(slot);
+        } else {
+            self.small.push_back(slot);
+        }
+    }
+
+    fn on_access(&mut self, slot: usize, slots: &mut Slots<'_>) {
+        let count = slots.count(slot);
+        if count < MAX_FREQUENCY {
+            slots.set_count(slot, count + 1);
+        }
+    }
+
+    fn on_remove(&mut self, vacated: &[usize], _: &mut Slots<'_>) {
+        for &slot in vacated {
+            self.vacating[slot] = true;
+        }
+        let vacating = &self.vacating;
+        self.small.retain(|&slot| !vacating[slot]);
+        self.main.retain(|&slot| !vacating[slot]);
+        for &slot in vacated {
+            self.vacating[slot] = false;
+        }
+    }
+
+    /// pop `small` while it is over its target, `main` otherwise, a read node get another
+    /// lap instead of leaving
+    fn victim(&mut self, _: u64, slots: &mut Slots<'_>) -> (usize, RemovalCause) {
+        loop {
+            if self.small.len() >= self.small_target || self.main.is_empty() {
+                let Some(slot) = self.small.pop_front() else {
+                    break;
+                };
+                if slots.outdated(slot) {
+                    return (slot, RemovalCause::Expired);
+                }
+                if slots.count(slot) > 0 {
+                    slots.set_count(slot, 0);
+                    self.main.push_back(slot);
+                    continue;
+                }
+                if let Some(hash) = slots.hash(slot) {
+                    self.haunt(hash);
+                }
+                self.from_small = Some(true);
+                return (slot, RemovalCause::Evicted);
+            }
+            let Some(slot) = self.main.pop_front() else {
+                break;
+            };
+            if slots.outdated(slot) {
+                return (slot, RemovalCause::Expired);
+            }
+            let count = slots.count(slot);
+            if count > 0 {
+                slots.set_count(slot, count - 1);
+                self.main.push_back(slot);
+                continue;
+            }
+            self.from_small = Some(false);
+            return (slot, RemovalCause::Evicted);
+        }
+        self.from_small = None;
+        (0, RemovalCause::Overwritten)
+    }
+
+    /// forget the ghost `victim` left and seat the slot back at the front of its queue
+    fn on_reject(&mut self, slot: usize, slots: &mut Slots<'_>) {
+        match self.from_small.take() {
+            Some(true) => {
+                if let Some(hash) = slots.hash(slot) {
+                    self.exorcise(hash);
+                }
+                self.small.push_front(slot);
+            }
+            Some(false) => self.main.push_front(slot),
+            None => {}
+        }
+    }
+
+    fn clear(&mut self) {
+        self.small.clear();
+        self.main.clear();
+        self.ghost.clear();
+        self.from_small = None;
+    }
+}
@@ -0,0 +1,164 @@
+use crate::EvictionPolicy;
+use crate::RemovalCause;
+use crate::Slots;
+
+/// end of the queue in `newer` and `older`
+const NIL: usize = usize::MAX;
+
+#[doc = r#"
+# Feature
+- **Visited bit**
+- **Moving hand**
+
+# Example
+## Visited bit
+an applied read only set `visited` of its slot, the queue is never reordered on a hit
+```
+use dual_cache_ff::{DualCacheFF, Sieve};
+
+let cache = DualCacheFF::builder()
+    .capacity(3)
+    .policy::<Sieve>()
+    .build_manual()
+    .unwrap();
+for i in 0..3 {
+    cache.put(i, i);
+}
+cache.get(&0);
+cache.run_pending_tasks();
+cache.put(3, 3);
+
+assert_eq!(cache.get(&0), Some(0));
+assert!(cache.get(&1).is_none());
+```
+
+## Moving hand
+`hand` walk from the oldest slot toward the newest, clearing `visited` as it pass, and stop
+at the first slot left unvisited, it resume there on the next eviction instead of the tail
+"#]
+pub struct Sieve {
+    visited: Vec<bool>,
+    newer: Vec<usize>,
+    older: Vec<usize>,
+    newest: usize,
+    oldest: usize,
+    hand: usize,
+}
+
+impl Sieve {
+    fn linked(&self, slot: usize) -> bool {
+        self.newest == slot || self.older[slot] != NIL || self.newer[slot] != NIL
+    }
+
+    /// push `slot` at the newest end of the queue
+    fn link(&mut self, slot: usize) {
+        self.older[slot] = self.newest;
+        self.newer[slot] = NIL;
+        match self.newest {
+            NIL => self.oldest = slot,
+            newest => self.newer[newest] = slot,
+        }
+        self.newest = slot;
+    }
+
+    /// put `slot` back just older than `newer`, at the newest end for `NIL`
+    fn link_before(&mut self, slot: usize, newer: usize) {
+        if newer == NIL {
+            return self.link(slot);
+        }
+        let older = self.older[newer];
+        self.older[slot] = older;
+        self.newer[slot] = newer;
+        self.older[newer] = slot;
+        match older {
+            NIL => self.oldest = slot,
+            older => self.newer[older] = slot,
+        }
+    }
+
+    /// take `slot` out of the queue, a `hand` on it step to the newer neighbour
+    fn unlink(&mut self, slot: usize) {
+        let (older, newer) = (self.older[slot], self.newer[slot]);
+        match older {
+            NIL => self.oldest = newer,
+            older => self.newer[older] = newer,
+        }
+        match newer {
+            NIL => self.newest = older,
+            newer => self.older[newer] = older,
+        }
+        if self.hand == slot {
+            self.hand = newer;
+        }
+        self.older[slot] = NIL;
+        self.newer[slot] = NIL;
+    }
+}
+
+impl EvictionPolicy for Sieve {
+    fn with_capacity(capacity: usize) -> Self {
+        Self {
+            visited: vec![false; capacity],
+            newer: vec![NIL; capacity],
+            older: vec![NIL; capacity],
+            newest: NIL,
+            oldest: NIL,
+            hand: NIL,
+        }
+    }
+
+    fn on_insert(&mut self, slot: usize, _: &mut Slots<'_>) {
+        self.visited[slot] = false;
+        self.link(slot);
+    }
+
+    fn on_access(&mut self, slot: usize, _: &mut Slots<'_>) {
+        self.visited[slot] = true;
+    }
+
+    fn on_remove(&mut self, vacated: &[usize], _: &mut Slots<'_>) {
+        for &slot in vacated {
+            if self.linked(slot) {
+                self.unlink(slot);
+            }
+        }
+    }
+
+    fn victim(&mut self, _: u64, slots: &mut Slots<'_>) -> (usize, RemovalCause) {
+        let mut hand = match self.hand {
+            NIL => self.oldest,
+            hand => hand,
+        };
+        let cause = loop {
+            if slots.outdated(hand) {
+                break RemovalCause::Expired;
+            }
+            if !self.visited[hand] {
+                break RemovalCause::Evicted;
+            }
+            self.visited[hand] = false;
+            hand = match self.newer[hand] {
+                NIL => self.oldest,
+                newer => newer,
+            };
+        };
+        self.hand = hand;
+        self.unlink(hand);
+        (hand, cause)
+    }
+
+    /// relink the slot where `victim` unlinked it, `hand` rest on it again
+    fn on_reject(&mut self, slot: usize, _: &mut Slots<'_>) {
+        self.link_before(slot, self.hand);
+        self.hand = slot;
+    }
+
+    fn clear(&mut self) {
+        self.visited.fill(false);
+        self.newer.fill(NIL);
+        self.older.fill(NIL);
+        self.newest = NIL;
+        self.oldest = NIL;
+        self.hand = NIL;
+    }
+}
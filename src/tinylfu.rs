@@ -0,0 +1,145 @@
+/// odd multipliers spreading one key hash over the rows of `TinyLfu::sketch`
+const SEEDS: [u64; 4] = [
+    0x9E37_79B9_7F4A_7C15,
+    0xC2B2_AE3D_27D4_EB4F,
+    0x1656_67B1_9E37_79F9,
+    0x27D4_EB2F_1656_67C5,
+];
+
+/// highest count a `sketch` counter reach
+const MAX_COUNT: u8 = 15;
+
+/// samples per slot before every counter is halved
+const SAMPLE_FACTOR: usize = 10;
+
+#[doc = r#"
+# Feature
+- **Count-min sketch**
+- **Doorkeeper**
+- **Periodic halving**
+
+# Example
+## Count-min sketch
+every key read through the lazy channel and every new key written is recorded, its frequency
+is the smallest of four counters so collisions only ever overestimate
+
+## Doorkeeper
+the first sighting of a key only set its bits in `doorkeeper`, one-hit wonders never reach
+`sketch`
+```
+use dual_cache_ff::DualCacheFF;
+
+let cache = DualCacheFF::builder()
+    .capacity(2)
+    .tiny_lfu()
+    .build_manual()
+    .unwrap();
+cache.put("A", 1);
+cache.put("B", 2);
+for _ in 0..3 {
+    cache.get("A");
+}
+cache.run_pending_tasks();
+
+cache.put("C", 3);
+assert!(cache.get("C").is_none());
+assert_eq!(cache.get("B"), Some(2));
+
+cache.put("C", 3);
+assert_eq!(cache.get("C"), Some(3));
+assert_eq!(cache.get("A"), Some(1));
+```
+
+## Periodic halving
+once `samples` reach ten per slot every counter is halved and `doorkeeper` is cleared, old
+popularity fade out instead of being dropped at once as `Cache::refresh` do with `Node::count`
+"#]
+pub(crate) struct TinyLfu {
+    sketch: Vec<[u8; 4]>,
+    doorkeeper: Vec<u64>,
+    /// bits of a `sketch` or `doorkeeper` index, both are `1 << shift` wide
+    shift: u32,
+    samples: usize,
+    sample_size: usize,
+}
+
+impl TinyLfu {
+    pub(crate) fn new(capacity: usize) -> Self {
+        let width = capacity.next_power_of_two().max(64);
+        Self {
+            sketch: vec![[0; 4]; width],
+            doorkeeper: vec![0; width / 64],
+            shift: width.trailing_zeros(),
+            samples: 0,
+            sample_size: capacity.saturating_mul(SAMPLE_FACTOR),
+        }
+    }
+
+    fn index(&self, hash: u64, row: usize) -> usize {
+        (hash.wrapping_mul(SEEDS[row]) >> (u64::BITS - self.shift)) as usize
+    }
+
+    /// set the `doorkeeper` bits of `hash`, telling whether they were all set already
+    fn admit_door(&mut self, hash: u64) -> bool {
+        let mut seen = true;
+        for row in 0..2 {
+            let bit = self.index(hash, row);
+            let word = &mut self.doorkeeper[bit / 64];
+            seen &= *word & (1 << (bit % 64)) != 0;
+            *word |= 1 << (bit % 64);
+        }
+        seen
+    }
+
+    fn in_door(&self, hash: u64) -> bool {
+        (0..2).all(|row| {
+            let bit = self.index(hash, row);
+            self.doorkeeper[bit / 64] & (1 << (bit % 64)) != 0
+        })
+    }
+
+    pub(crate) fn record(&mut self, hash: u64) {
+        if self.admit_door(hash) {
+            for row in 0..SEEDS.len() {
+                let index = self.index(hash, row);
+                let counter = &mut self.sketch[index][row];
+                *counter = (*counter + 1).min(MAX_COUNT);
+            }
+        }
+        self.samples += 1;
+        if self.samples >= self.sample_size {
+            self.halve();
+        }
+    }
+
+    pub(crate) fn frequency(&self, hash: u64) -> u8 {
+        let count = (0..SEEDS.len())
+            .map(|row| self.sketch[self.index(hash, row)][row])
+            .min()
+            .unwrap_or(0);
+        count + u8::from(self.in_door(hash))
+    }
+
+    /// whether `candidate` was seen more often than the `victim` it would replace
+    pub(crate) fn admit(&self, candidate: u64, victim: u64) -> bool {
+        self.frequency(candidate) > self.frequency(victim)
+    }
+
+    fn halve(&mut self) {
+        for counters in &mut self.sketch {
+            for counter in counters {
+                *counter >>= 1;
+            }
+        }
+        self.doorkeeper.fill(0);
+        self.samples /= 2;
+    }
+
+    pub(crate) fn clear(&mut self) {
+        for counters in &mut self.sketch {
+            *counters = [0; 4];
+        }
+        self.doorkeeper.fill(0);
+        self.samples = 0;
+    }
+}
@@ -0,0 +1,102 @@
+use std::time::Duration;
+
+const NIL: usize = usize::MAX;
+const BUCKETS: usize = 64;
+/// tick of each level in nanoseconds as a power of two, ~67ms, ~4.3s, ~4.6min, ~4.9h
+const SHIFT: [u32; 4] = [26, 32, 38, 44];
+
+/// hierarchical timer wheel over `nodes` slots, bucket lists are threaded through per slot
+/// `next` and `prev` so scheduling and cancelling are O(1)
+pub(crate) struct TimerWheel {
+    head: Vec<usize>,
+    next: Vec<usize>,
+    prev: Vec<usize>,
+    seat: Vec<usize>,
+    clock: u64,
+}
+
+impl TimerWheel {
+    pub(crate) fn new(capacity: usize, now: Duration) -> Self {
+        Self {
+            head: vec![NIL; BUCKETS * SHIFT.len()],
+            next: vec![NIL; capacity],
+            prev: vec![NIL; capacity],
+            seat: vec![NIL; capacity],
+            clock: nanos(now),
+        }
+    }
+
+    /// (re)arm `slot` to fire once `advance` pass `deadline`
+    pub(crate) fn schedule(&mut self, slot: usize, deadline: Duration) {
+        self.cancel(slot);
+        // an overdue timer fire on the next tick instead of a full rotation later
+        let deadline = nanos(deadline).max(self.clock + (1 << SHIFT[0]));
+        let delta = deadline - self.clock;
+        let level = (1..SHIFT.len())
+            .find(|&level| delta < 1 << SHIFT[level])
+            .map_or(SHIFT.len() - 1, |level| level - 1);
+        let bucket = level * BUCKETS + ((deadline >> SHIFT[level]) as usize & (BUCKETS - 1));
+
+        self.next[slot] = self.head[bucket];
+        self.prev[slot] = NIL;
+        if self.head[bucket] != NIL {
+            self.prev[self.head[bucket]] = slot;
+        }
+        self.head[bucket] = slot;
+        self.seat[slot] = bucket;
+    }
+
+    pub(crate) fn cancel(&mut self, slot: usize) {
+        let bucket = self.seat[slot];
+        if bucket == NIL {
+            return;
+        }
+        let (prev, next) = (self.prev[slot], self.next[slot]);
+        if prev == NIL {
+            self.head[bucket] = next;
+        } else {
+            self.next[prev] = next;
+        }
+        if next != NIL {
+            self.prev[next] = prev;
+        }
+        self.seat[slot] = NIL;
+    }
+
+    /// move the wheel to `now` and hand back every slot of the buckets swept on the way,
+    /// the caller check them against their node and reschedule the ones not due yet
+    pub(crate) fn advance(&mut self, now: Duration) -> Vec<usize> {
+        let now = nanos(now);
+        let mut due = Vec::new();
+        if now <= self.clock {
+            return due;
+        }
+
+        for (level, shift) in SHIFT.iter().enumerate() {
+            let (from, to) = (self.clock >> shift, now >> shift);
+            if from == to {
+                break;
+            }
+            for tick in from..=to.min(from + BUCKETS as u64 - 1) {
+                let bucket = level * BUCKETS + (tick as usize & (BUCKETS - 1));
+                let mut slot = std::mem::replace(&mut self.head[bucket], NIL);
+                while slot != NIL {
+                    self.seat[slot] = NIL;
+                    due.push(slot);
+                    slot = self.next[slot];
+                }
+            }
+        }
+        self.clock = now;
+        due
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.head.fill(NIL);
+        self.seat.fill(NIL);
+    }
+}
+
+fn nanos(at: Duration) -> u64 {
+    at.as_nanos().min(u64::MAX as u128) as u64
+}
@@ -0,0 +1,299 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fmt::Debug;
+use std::hash::BuildHasher;
+use std::hash::Hash;
+use std::hash::RandomState;
+use std::panic::AssertUnwindSafe;
+use std::sync::Arc;
+use std::sync::Condvar;
+use std::sync::Mutex;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+use tracing::warn;
+
+use crate::CacheLoader;
+use crate::DualCacheFF;
+use crate::EvictionPolicy;
+
+/// when `DualCacheFF` hand its writes to the `CacheWriter`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WriteMode {
+    /// synchronously inside `put` and `remove`, outside `main`, a failed write is not cached
+    Through,
+    /// queued for the daemon, coalesced per key and flushed in batches
+    Behind,
+}
+
+#[doc = r#"
+# Feature
+- **Write through**
+- **Write behind**
+
+# Example
+## Write through
+`DualCacheFF::try_put` write the store first and only cache what it accepted
+```
+use std::sync::Arc;
+use dual_cache_ff::{DualCacheFF, MemoryStore, WriteMode};
+
+let store = Arc::new(MemoryStore::new());
+let cache = DualCacheFF::builder()
+    .writer(store.clone(), WriteMode::Through)
+    .build_manual()
+    .unwrap();
+cache.put("A", 100);
+cache.remove("A");
+cache.put("B", 200);
+
+assert_eq!(store.get(&"A"), None);
+assert_eq!(store.get(&"B"), Some(200));
+```
+
+## Write behind
+changes wait in the daemon queue, repeated writes of a key collapse into the last one before
+`write_all` see them
+```
+use std::sync::Arc;
+use dual_cache_ff::{DualCacheFF, MemoryStore, WriteMode};
+
+let store = Arc::new(MemoryStore::new());
+let cache = DualCacheFF::builder()
+    .writer(store.clone(), WriteMode::Behind)
+    .build_manual()
+    .unwrap();
+for i in 0..10 {
+    cache.put("A", i);
+}
+assert_eq!(store.get(&"A"), None);
+
+cache.run_pending_tasks();
+assert_eq!(store.get(&"A"), Some(9));
+assert_eq!(store.writes(), 1);
+```
+"#]
+pub trait CacheWriter<K, V>: Send + Sync {
+    fn write(&self, key: &K, value: &V) -> anyhow::Result<()>;
+
+    fn delete(&self, key: &K) -> anyhow::Result<()>;
+
+    /// flush one write-behind batch, `None` stand for a delete
+    fn write_all(&self, batch: &[(K, Option<V>)]) -> anyhow::Result<()> {
+        for (key, value) in batch {
+            match value {
+                Some(value) => self.write(key, value)?,
+                None => self.delete(key)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<K, V, W: CacheWriter<K, V> + ?Sized> CacheWriter<K, V> for std::sync::Arc<W> {
+    fn write(&self, key: &K, value: &V) -> anyhow::Result<()> {
+        (**self).write(key, value)
+    }
+
+    fn delete(&self, key: &K) -> anyhow::Result<()> {
+        (**self).delete(key)
+    }
+
+    fn write_all(&self, batch: &[(K, Option<V>)]) -> anyhow::Result<()> {
+        (**self).write_all(batch)
+    }
+}
+
+/// in-memory backing store, stand in for a database in tests and examples
+pub struct MemoryStore<K, V> {
+    entries: Mutex<HashMap<K, V>>,
+    writes: AtomicUsize,
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> MemoryStore<K, V> {
+    pub fn new() -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+            writes: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn get(&self, key: &K) -> Option<V> {
+        self.entries.lock().unwrap().get(key).cloned()
+    }
+
+    /// number of `write` and `delete` applied so far
+    pub fn writes(&self) -> usize {
+        self.writes.load(Ordering::SeqCst)
+    }
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> Default for MemoryStore<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> CacheWriter<K, V> for MemoryStore<K, V>
+where
+    K: Hash + Eq + Clone + Send,
+    V: Clone + Send,
+{
+    fn write(&self, key: &K, value: &V) -> anyhow::Result<()> {
+        self.writes.fetch_add(1, Ordering::SeqCst);
+        self.entries
+            .lock()
+            .unwrap()
+            .insert(key.clone(), value.clone());
+        Ok(())
+    }
+
+    fn delete(&self, key: &K) -> anyhow::Result<()> {
+        self.writes.fetch_add(1, Ordering::SeqCst);
+        self.entries.lock().unwrap().remove(key);
+        Ok(())
+    }
+}
+
+impl<K, V> CacheLoader<K, V> for MemoryStore<K, V>
+where
+    K: Hash + Eq + Clone + Send + Debug,
+    V: Clone + Send,
+{
+    fn load(&self, key: &K) -> anyhow::Result<V> {
+        self.get(key)
+            .ok_or_else(|| anyhow::anyhow!("{key:?} not found in store"))
+    }
+}
+
+/// hashes of the keys a change is being applied to, so the changes of a key reach the
+/// `CacheWriter` and the cache in the same order without holding `main` over store I/O
+pub(crate) struct KeyLocks {
+    hasher: RandomState,
+    held: Mutex<HashSet<u64>>,
+    released: Condvar,
+}
+
+impl KeyLocks {
+    pub(crate) fn new() -> Self {
+        Self {
+            hasher: RandomState::new(),
+            held: Mutex::new(HashSet::new()),
+            released: Condvar::new(),
+        }
+    }
+
+    /// block until no other thread hold `key`, keys sharing a hash wait on each other too
+    pub(crate) fn lock<Q: Hash + ?Sized>(&self, key: &Q) -> KeyGuard<'_> {
+        let hash = self.hasher.hash_one(key);
+        let mut held = self
+            .released
+            .wait_while(self.held.lock().unwrap(), |held| held.contains(&hash))
+            .unwrap();
+        held.insert(hash);
+        KeyGuard { locks: self, hash }
+    }
+}
+
+/// release the key on drop, unwinding included
+pub(crate) struct KeyGuard<'a> {
+    locks: &'a KeyLocks,
+    hash: u64,
+}
+
+impl Drop for KeyGuard<'_> {
+    fn drop(&mut self) {
+        self.locks.held.lock().unwrap().remove(&self.hash);
+        self.locks.released.notify_all();
+    }
+}
+
+/// write-behind side of `DualCacheFF`, shared with the daemon so it can still flush what was
+/// queued once the last `Arc<DualCacheFF>` dropped
+pub(crate) struct Behind<K, V> {
+    writer: Arc<dyn CacheWriter<K, V>>,
+    /// changes of a failed `write_all`, retried ahead of newer ones
+    failed: Mutex<Vec<(K, Option<V>)>>,
+}
+
+impl<K: Hash + Eq + Clone, V> Behind<K, V> {
+    pub(crate) fn new(writer: Arc<dyn CacheWriter<K, V>>) -> Self {
+        Self {
+            writer,
+            failed: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// coalesce the failed changes and `changes` per key and flush them in one `write_all`,
+    /// a failed batch is kept for the next flush, a panicking one is logged and dropped
+    pub(crate) fn flush(&self, changes: impl Iterator<Item = (K, Option<V>)>) -> usize {
+        let mut processed_count = 0;
+        let mut failed = self.failed.lock().unwrap();
+        let mut order = Vec::new();
+        let mut latest = HashMap::new();
+        let changes = changes.inspect(|_| processed_count += 1);
+        for (key, value) in failed.drain(..).chain(changes) {
+            if latest.insert(key.clone(), value).is_none() {
+                order.push(key);
+            }
+        }
+        if order.is_empty() {
+            return processed_count;
+        }
+
+        let batch: Vec<_> = order
+            .into_iter()
+            .map(|key| {
+                let value = latest.remove(&key).flatten();
+                (key, value)
+            })
+            .collect();
+        let written = std::panic::catch_unwind(AssertUnwindSafe(|| self.writer.write_all(&batch)));
+        match written {
+            Ok(Ok(())) => {}
+            Ok(Err(err)) => {
+                warn!(%err, size = batch.len(), "write-behind batch failed, retried on next flush");
+                *failed = batch;
+            }
+            // a panic is not transient, retrying would only panic again
+            Err(_) => warn!(size = batch.len(), "cache writer panicked, batch dropped"),
+        }
+        processed_count
+    }
+
+    /// changes of failed batches still waiting for a retry
+    pub(crate) fn pending(&self) -> usize {
+        self.failed.lock().unwrap().len()
+    }
+}
+
+impl<K, V, P> DualCacheFF<K, V, P>
+where
+    K: Hash + Eq + Clone + Send + Sync + 'static + Debug,
+    V: Clone + Send + Sync + 'static + Debug,
+    P: EvictionPolicy,
+{
+    /// hand a change to the configured `CacheWriter`, only a write-through failure come back
+    pub(crate) fn propagate(&self, key: &K, value: Option<&V>) -> anyhow::Result<()> {
+        match &self.hooks.writer {
+            None => Ok(()),
+            Some((writer, WriteMode::Through)) => match value {
+                Some(value) => writer.write(key, value),
+                None => writer.delete(key),
+            },
+            Some((_, WriteMode::Behind)) => {
+                // the receiver lives as long as `self`
+                let _ = self.behind_tx.send((key.clone(), value.cloned()));
+                Ok(())
+            }
+        }
+    }
+
+    /// flush queued write-behind changes, along with any batch that failed before
+    pub(crate) fn write_behind(&self, changes: impl Iterator<Item = (K, Option<V>)>) -> usize {
+        match &self.behind {
+            Some(behind) => behind.flush(changes),
+            None => changes.count(),
+        }
+    }
+}